@@ -1,11 +1,21 @@
 //! Tauri commands for frontend communication
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 use crate::providers::{ProviderRegistry, AuthFlow, AuthResponse};
-use crate::storage::{CacheManager, UsageData, ModelQuota, keyring};
+use crate::providers::health::{HealthSnapshot, ProviderHealth};
+use crate::providers::retry::RetryStatus;
+use crate::config::{self, AppConfig, ConfigManager};
+use crate::notifications::{self, rules::{AlertRule, Severity}, sinks::{self, Notification, SinkConfig}};
+use crate::notifications::digest::{self, DigestSettings};
+use crate::notifications::quiet::{DndStatus, DoNotDisturb, QuietHours};
+use crate::notifications::templates::{self, MessageTemplate, TemplateSettings};
+use crate::scheduler::{self, RefreshInterval, Scheduler, SchedulerStatus};
+use crate::tray::{self, icon::TrayTheme};
+use crate::storage::{CacheManager, UsageCache, UsageData, ModelQuota, backup, credentials, keyring, vault};
 
 #[derive(serde::Serialize)]
 pub struct ProviderStatus {
@@ -20,6 +30,13 @@ pub struct ProviderStatus {
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model_quotas: Option<Vec<ModelQuota>>,
+    /// Cached data predates a resume or network change and hasn't been refreshed yet
+    pub stale: bool,
+    /// Set once the scheduler has polled the provider; carries the next attempt time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<HealthSnapshot>,
 }
 
 impl From<(&str, &UsageData, bool, bool)> for ProviderStatus {
@@ -35,6 +52,9 @@ impl From<(&str, &UsageData, bool, bool)> for ProviderStatus {
             reset_time: data.reset_time.map(|t| t.to_rfc3339()),
             error: data.error.clone(),
             model_quotas: data.model_quotas.clone(),
+            stale: false,
+            retry: None,
+            health: None,
         }
     }
 }
@@ -60,9 +80,11 @@ pub async fn start_provider_auth(
 
 #[tauri::command]
 pub async fn complete_provider_auth(
+    app: AppHandle,
     provider: String,
     response: AuthResponse,
     registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+    scheduler: State<'_, Arc<Scheduler>>,
 ) -> Result<(), String> {
     // Get provider from registry
     let provider_arc = {
@@ -72,7 +94,10 @@ pub async fn complete_provider_auth(
     
     if let Some(p_arc) = provider_arc {
         let mut p = p_arc.write().await;
-        p.complete_auth(response).await.map_err(|e| e.to_string())
+        p.complete_auth(response).await.map_err(|e| e.to_string())?;
+        scheduler.clear_auth(&provider).await;
+        notifications::auth::clear_auth_failure(&app, &provider).await;
+        Ok(())
     } else {
         Err(format!("Provider '{}' not found", provider))
     }
@@ -83,77 +108,107 @@ pub async fn get_provider_status(
     provider: String,
     cache: State<'_, Arc<RwLock<CacheManager>>>,
     registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+    scheduler: State<'_, Arc<Scheduler>>,
 ) -> Result<ProviderStatus, String> {
-    let cache = cache.read().await;
-    let registry_read = registry.read().await;
-    
-    let enabled = registry_read.is_enabled(&provider);
-    let data = cache.get(&provider).cloned().unwrap_or_default();
+    // Copy what we need so neither lock is held while querying the provider
+    let (enabled, provider_arc) = {
+        let registry_read = registry.read().await;
+        (registry_read.is_enabled(&provider), registry_read.get_provider(&provider))
+    };
+    let (data, stale) = {
+        let cache = cache.read().await;
+        (cache.get(&provider).cloned().unwrap_or_default(), cache.is_stale(&provider))
+    };
     
-    let authenticated = if let Some(p_arc) = registry_read.get_provider(&provider) {
+    let authenticated = if let Some(p_arc) = provider_arc {
         let p = p_arc.read().await;
         p.is_authenticated().await
     } else {
         false
     };
     
-    Ok(ProviderStatus::from((provider.as_str(), &data, enabled, authenticated)))
+    let mut status = ProviderStatus::from((provider.as_str(), &data, enabled, authenticated));
+    status.stale = stale;
+    status.retry = scheduler.retry_status(&provider).await;
+    status.health = scheduler.health(&provider).await;
+    Ok(status)
 }
 
 #[tauri::command]
 pub async fn get_all_usage(
     cache: State<'_, Arc<RwLock<CacheManager>>>,
     registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+    scheduler: State<'_, Arc<Scheduler>>,
 ) -> Result<Vec<ProviderStatus>, String> {
-    let cache = cache.read().await;
-    let registry_read = registry.read().await;
+    // Copy what we need so neither lock is held while querying providers
+    let providers: Vec<_> = {
+        let registry_read = registry.read().await;
+        registry_read
+            .all_provider_names()
+            .into_iter()
+            .map(|name| {
+                let enabled = registry_read.is_enabled(&name);
+                let provider_arc = registry_read.get_provider(&name);
+                (name, enabled, provider_arc)
+            })
+            .collect()
+    };
+    let (cached, stale): (HashMap<_, _>, Vec<_>) = {
+        let cache = cache.read().await;
+        let stale = providers.iter().filter(|(name, ..)| cache.is_stale(name)).map(|(name, ..)| name.clone()).collect();
+        (cache.get_all().clone(), stale)
+    };
     
     let mut statuses = Vec::new();
     
-    for name in registry_read.all_provider_names() {
-        let enabled = registry_read.is_enabled(&name);
-        let data = cache.get(&name).cloned().unwrap_or_default();
+    for (name, enabled, provider_arc) in providers {
+        let data = cached.get(&name).cloned().unwrap_or_default();
         
-        let authenticated = if let Some(p_arc) = registry_read.get_provider(&name) {
+        let authenticated = if let Some(p_arc) = provider_arc {
             let p = p_arc.read().await;
             p.is_authenticated().await
         } else {
             false
         };
         
-        statuses.push(ProviderStatus::from((name.as_str(), &data, enabled, authenticated)));
+        let mut status = ProviderStatus::from((name.as_str(), &data, enabled, authenticated));
+        status.stale = stale.contains(&name);
+        status.retry = scheduler.retry_status(&name).await;
+        status.health = scheduler.health(&name).await;
+        statuses.push(status);
     }
     
     Ok(statuses)
 }
 
+/// Refresh one provider and wait for its status; shares the scheduler's claim
+/// and timeout, so it never overlaps a scheduled fetch
 #[tauri::command]
 pub async fn refresh_provider(
+    app: AppHandle,
     provider: String,
     cache: State<'_, Arc<RwLock<CacheManager>>>,
-    registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+    scheduler: State<'_, Arc<Scheduler>>,
 ) -> Result<ProviderStatus, String> {
-    // Get provider from registry (holding lock briefly)
-    let provider_arc = {
-        let registry_guard = registry.read().await;
-        registry_guard.get_provider(&provider)
-    };
+    let usage = scheduler::refresh_provider_now(&app, &provider).await?;
     
-    if let Some(p_arc) = provider_arc {
-        let p = p_arc.read().await;
-        match p.fetch_usage().await {
-            Ok(usage) => {
-                let mut cache = cache.write().await;
-                cache.set(&provider, usage.clone());
-                let _ = cache.save();
-                
-                Ok(ProviderStatus::from((provider.as_str(), &usage, true, true)))
-            }
-            Err(e) => Err(e.to_string()),
-        }
-    } else {
-        Err(format!("Provider '{}' not found", provider))
-    }
+    let mut status = ProviderStatus::from((provider.as_str(), &usage, true, true));
+    status.stale = cache.read().await.is_stale(&provider);
+    status.retry = scheduler.retry_status(&provider).await;
+    status.health = scheduler.health(&provider).await;
+    Ok(status)
+}
+
+/// Fetch outcomes, latency and recent errors for a provider
+#[tauri::command]
+pub async fn get_provider_health(
+    provider: String,
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<HealthSnapshot, String> {
+    Ok(scheduler
+        .health(&provider)
+        .await
+        .unwrap_or_else(|| ProviderHealth::default().snapshot()))
 }
 
 #[tauri::command]
@@ -161,20 +216,33 @@ pub async fn save_credentials(
     provider: String,
     credential_type: String,
     value: String,
+    scheduler: State<'_, Arc<Scheduler>>,
 ) -> Result<(), String> {
     let key = format!("{}_{}", provider, credential_type);
+    if !keyring::keys::is_provider_credential(&key) {
+        return Err(format!("Unknown credential '{}'", key));
+    }
     keyring::store_credential(&key, &value)
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    scheduler.clear_auth(&provider).await;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn set_provider_enabled(
+    app: AppHandle,
     provider: String,
     enabled: bool,
     registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
 ) -> Result<(), String> {
-    let mut registry = registry.write().await;
-    registry.set_enabled(&provider, enabled);
+    registry.write().await.set_enabled(&provider, enabled);
+    config
+        .write()
+        .await
+        .update(|c| c.set_provider_enabled(&provider, enabled))
+        .map_err(|e| e.to_string())?;
+    tray::refresh(&app).await;
     Ok(())
 }
 
@@ -202,6 +270,9 @@ pub struct AuthStatusResponse {
     pub user: Option<String>,
     pub plan: Option<String>,
     pub expires: Option<String>,
+    /// Why stored credentials stopped working (e.g. a rejected token refresh)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 #[tauri::command]
@@ -239,16 +310,520 @@ pub async fn get_provider_auth_status(
                     user: email,
                     plan,
                     expires,
+                    error: None,
                 })
             }
+            crate::providers::traits::AuthStatus::RefreshFailed { message }
+            | crate::providers::traits::AuthStatus::Error { message } => Ok(AuthStatusResponse {
+                authenticated: false,
+                user: None,
+                plan: None,
+                expires: None,
+                error: Some(message),
+            }),
             _ => Ok(AuthStatusResponse {
                 authenticated: false,
                 user: None,
                 plan: None,
                 expires: None,
+                error: None,
             }),
         }
     } else {
         Err(format!("Provider '{}' not found", provider))
     }
 }
+
+#[tauri::command]
+pub async fn get_vault_status() -> Result<vault::VaultStatus, String> {
+    vault::with_vault(|v| v.status()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn enable_vault(passphrase: String) -> Result<(), String> {
+    keyring::enable_vault(&passphrase).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn disable_vault(passphrase: String) -> Result<(), String> {
+    keyring::disable_vault(&passphrase).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unlock_vault(
+    app: AppHandle,
+    passphrase: String,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<(), String> {
+    vault::unlock(passphrase).await.map_err(|e| e.to_string())?;
+    config.write().await.unseal_sinks();
+    scheduler::reload_provider_credentials(&app).await;
+    app.state::<Arc<Scheduler>>().clear_all_auth().await;
+    let _ = app.emit("vault-unlocked", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn lock_vault(app: AppHandle) -> Result<(), String> {
+    vault::with_vault(|v| v.lock()).map_err(|e| e.to_string())?;
+    scheduler::reload_provider_credentials(&app).await;
+    let _ = app.emit("vault-locked", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn change_vault_passphrase(current: String, new: String) -> Result<(), String> {
+    vault::change_passphrase(current, new).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_vault_auto_lock(minutes: Option<u64>) -> Result<(), String> {
+    vault::with_vault(|v| v.set_auto_lock(minutes))
+        .and_then(|r| r)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_credential_backend() -> Result<credentials::BackendInfo, String> {
+    credentials::info().ok_or_else(|| "Credential store not initialized".to_string())
+}
+
+/// Switch credential backend (`None` = automatic), migrating stored entries
+#[tauri::command]
+pub async fn set_credential_backend(
+    backend: Option<credentials::BackendKind>,
+) -> Result<credentials::BackendInfo, String> {
+    credentials::set_preferred(backend).await.map_err(|e| e.to_string())?;
+    credentials::info().ok_or_else(|| "Credential store not initialized".to_string())
+}
+
+/// Recover the encrypted credentials file after a machine identity change,
+/// given the previous `username@hostname` or the passphrase it was sealed with
+#[tauri::command]
+pub async fn recover_credentials(
+    app: AppHandle,
+    from: credentials::RecoveryKey,
+) -> Result<credentials::BackendInfo, String> {
+    credentials::recover_encrypted_file(from).await.map_err(|e| e.to_string())?;
+    scheduler::reload_provider_credentials(&app).await;
+    app.state::<Arc<Scheduler>>().clear_all_auth().await;
+    credentials::info().ok_or_else(|| "Credential store not initialized".to_string())
+}
+
+/// Export enablement, settings, cache and (optionally) credentials to an encrypted archive
+#[tauri::command]
+pub async fn export_backup(
+    path: String,
+    passphrase: String,
+    include_secrets: bool,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    cache: State<'_, Arc<RwLock<CacheManager>>>,
+    registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+) -> Result<backup::BackupSummary, String> {
+    let provider_enabled = {
+        let registry = registry.read().await;
+        registry
+            .all_provider_names()
+            .into_iter()
+            .map(|name| {
+                let enabled = registry.is_enabled(&name);
+                (name, enabled)
+            })
+            .collect()
+    };
+    
+    let credentials = if include_secrets {
+        Some(keyring::export_credentials().map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+    
+    let mut contents = backup::BackupContents {
+        created_at: chrono::Utc::now(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        provider_enabled,
+        config: Some(config.read().await.get().clone()),
+        cache: UsageCache {
+            providers: cache.read().await.get_all().clone(),
+        },
+        credentials,
+    };
+    if !include_secrets {
+        contents.strip_secrets();
+    }
+    
+    backup::write_backup(std::path::Path::new(&path), &contents, &passphrase)
+        .map_err(|e| e.to_string())?;
+    Ok(contents.summary())
+}
+
+/// Validate and restore an archive written by `export_backup`
+#[tauri::command]
+pub async fn import_backup(
+    app: AppHandle,
+    path: String,
+    passphrase: String,
+    include_secrets: bool,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    cache: State<'_, Arc<RwLock<CacheManager>>>,
+    registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+) -> Result<backup::BackupSummary, String> {
+    let mut contents = backup::read_backup(std::path::Path::new(&path), &passphrase)
+        .map_err(|e| e.to_string())?;
+    
+    // Secrets first: if they can't be stored (e.g. locked vault) nothing else changes
+    if include_secrets {
+        if let Some(entries) = &contents.credentials {
+            keyring::import_credentials(entries).map_err(|e| e.to_string())?;
+        }
+    } else {
+        contents.strip_secrets();
+    }
+    
+    match &contents.config {
+        Some(restored) => {
+            let known = registry.read().await.all_provider_names();
+            let mut config = config.write().await;
+            // Archives from other builds may name providers this one lacks; repair instead of rejecting
+            let mut restored = restored.clone();
+            restored.normalize(&known);
+            config.set(restored, &known).map_err(|e| e.to_string())?;
+            let scheduler = app.state::<Arc<Scheduler>>();
+            config::apply(config.get(), &registry, &scheduler).await;
+        }
+        None => {
+            let mut registry = registry.write().await;
+            for (name, enabled) in &contents.provider_enabled {
+                registry.set_enabled(name, *enabled);
+            }
+        }
+    }
+    
+    {
+        let mut cache = cache.write().await;
+        for (name, data) in &contents.cache.providers {
+            cache.set(name, data.clone());
+        }
+        cache.save().map_err(|e| e.to_string())?;
+    }
+    
+    scheduler::reload_provider_credentials(&app).await;
+    let _ = app.emit("backup-restored", ());
+    Ok(contents.summary())
+}
+
+#[tauri::command]
+pub async fn get_config(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<AppConfig, String> {
+    Ok(config.read().await.get().clone())
+}
+
+/// Validate, persist and apply a new configuration
+#[tauri::command]
+pub async fn set_config(
+    app: AppHandle,
+    new_config: AppConfig,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<AppConfig, String> {
+    let known = registry.read().await.all_provider_names();
+    let applied = {
+        let mut config = config.write().await;
+        config.set(new_config, &known).map_err(|e| e.to_string())?;
+        config::apply(config.get(), &registry, &scheduler).await;
+        config.get().clone()
+    };
+    tray::refresh(&app).await;
+    Ok(applied)
+}
+
+#[tauri::command]
+pub async fn get_refresh_interval(
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<RefreshInterval, String> {
+    Ok(scheduler.interval().await)
+}
+
+/// Change the global refresh interval and persist it
+#[tauri::command]
+pub async fn set_refresh_interval(
+    interval: RefreshInterval,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<(), String> {
+    config
+        .write()
+        .await
+        .update(|c| c.refresh_interval = interval)
+        .map_err(|e| e.to_string())?;
+    scheduler.set_interval(interval).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_alert_rules(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<Vec<AlertRule>, String> {
+    Ok(config.read().await.get().alert_rules.clone())
+}
+
+/// Replace the alert rules and persist them
+#[tauri::command]
+pub async fn set_alert_rules(
+    rules: Vec<AlertRule>,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+) -> Result<Vec<AlertRule>, String> {
+    let known = registry.read().await.all_provider_names();
+    let mut config = config.write().await;
+    let mut updated = config.get().clone();
+    updated.alert_rules = rules;
+    updated.validate(&known).map_err(|e| e.to_string())?;
+    config.update(|c| c.alert_rules = updated.alert_rules).map_err(|e| e.to_string())?;
+    Ok(config.get().alert_rules.clone())
+}
+
+#[tauri::command]
+pub async fn get_notification_sinks(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<Vec<SinkConfig>, String> {
+    Ok(config.read().await.get().notification_sinks.clone())
+}
+
+/// Replace the external notification sinks and persist them
+#[tauri::command]
+pub async fn set_notification_sinks(
+    sinks: Vec<SinkConfig>,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+) -> Result<Vec<SinkConfig>, String> {
+    let known = registry.read().await.all_provider_names();
+    let mut config = config.write().await;
+    let mut updated = config.get().clone();
+    updated.notification_sinks = sinks;
+    updated.validate(&known).map_err(|e| e.to_string())?;
+    config.update(|c| c.notification_sinks = updated.notification_sinks).map_err(|e| e.to_string())?;
+    Ok(config.get().notification_sinks.clone())
+}
+
+/// Send a test message through a (possibly unsaved) sink
+#[tauri::command]
+pub async fn test_notification_sink(sink: SinkConfig) -> Result<(), String> {
+    sink.validate().map_err(|e| e.to_string())?;
+    let notification = Notification::new(
+        "test",
+        Severity::Info,
+        None,
+        "LimitsWatcher",
+        "Test notification from LimitsWatcher",
+    );
+    sinks::deliver(sink.build().as_ref(), &notification)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_quiet_hours(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<QuietHours, String> {
+    Ok(config.read().await.get().quiet_hours.clone())
+}
+
+/// Replace the quiet-hours schedule and persist it
+#[tauri::command]
+pub async fn set_quiet_hours(
+    app: AppHandle,
+    quiet_hours: QuietHours,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<QuietHours, String> {
+    quiet_hours.validate()?;
+    config
+        .write()
+        .await
+        .update(|c| c.quiet_hours = quiet_hours.clone())
+        .map_err(|e| e.to_string())?;
+    // Shortening or disabling the schedule may end quiet time right away
+    notifications::release_held(&app).await;
+    Ok(quiet_hours)
+}
+
+#[tauri::command]
+pub async fn get_dnd_status(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    dnd: State<'_, Arc<RwLock<DoNotDisturb>>>,
+) -> Result<DndStatus, String> {
+    let hours = config.read().await.get().quiet_hours.clone();
+    Ok(dnd.read().await.status(&hours, chrono::Utc::now()))
+}
+
+/// Hold non-critical notifications for the next `hours` hours
+#[tauri::command]
+pub async fn snooze_notifications(
+    hours: f64,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    dnd: State<'_, Arc<RwLock<DoNotDisturb>>>,
+) -> Result<DndStatus, String> {
+    if !(hours > 0.0 && hours <= 24.0 * 7.0) {
+        return Err("Snooze must be between 0 and 168 hours".to_string());
+    }
+    let now = chrono::Utc::now();
+    let until = now + chrono::Duration::seconds((hours * 3600.0) as i64);
+    let quiet_hours = config.read().await.get().quiet_hours.clone();
+    let mut dnd = dnd.write().await;
+    dnd.snooze(until);
+    Ok(dnd.status(&quiet_hours, now))
+}
+
+/// End a snooze early and deliver anything held, unless quiet hours still apply
+#[tauri::command]
+pub async fn cancel_snooze(
+    app: AppHandle,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    dnd: State<'_, Arc<RwLock<DoNotDisturb>>>,
+) -> Result<DndStatus, String> {
+    dnd.write().await.cancel_snooze();
+    notifications::release_held(&app).await;
+    let hours = config.read().await.get().quiet_hours.clone();
+    Ok(dnd.read().await.status(&hours, chrono::Utc::now()))
+}
+
+#[tauri::command]
+pub async fn get_digest_settings(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<DigestSettings, String> {
+    Ok(config.read().await.get().digest.clone())
+}
+
+/// Change the digest schedule and persist it
+#[tauri::command]
+pub async fn set_digest_settings(
+    settings: DigestSettings,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+) -> Result<DigestSettings, String> {
+    let known = registry.read().await.all_provider_names();
+    let mut config = config.write().await;
+    let mut updated = config.get().clone();
+    updated.digest = settings.clone();
+    updated.validate(&known).map_err(|e| e.to_string())?;
+    config.update(|c| c.digest = settings.clone()).map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_notification_templates(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<TemplateSettings, String> {
+    Ok(config.read().await.get().templates.clone())
+}
+
+/// Built-in text for every message kind, for editing starting points
+#[tauri::command]
+pub fn get_default_notification_templates() -> HashMap<String, MessageTemplate> {
+    templates::KINDS
+        .iter()
+        .filter_map(|kind| templates::default_template(kind).map(|t| (kind.to_string(), t)))
+        .collect()
+}
+
+/// Change notification text overrides and time format, and persist them
+#[tauri::command]
+pub async fn set_notification_templates(
+    settings: TemplateSettings,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<TemplateSettings, String> {
+    settings.validate()?;
+    config
+        .write()
+        .await
+        .update(|c| c.templates = settings.clone())
+        .map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_tray_theme(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<TrayTheme, String> {
+    Ok(config.read().await.get().tray_theme)
+}
+
+/// Change the tray icon variant, persist it and redraw the icon
+#[tauri::command]
+pub async fn set_tray_theme(
+    app: AppHandle,
+    theme: TrayTheme,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<TrayTheme, String> {
+    config
+        .write()
+        .await
+        .update(|c| c.tray_theme = theme)
+        .map_err(|e| e.to_string())?;
+    tray::refresh(&app).await;
+    Ok(theme)
+}
+
+/// Current tray icon as PNG, e.g. to preview a variant in settings
+#[tauri::command]
+pub async fn get_tray_icon_png(
+    app: AppHandle,
+    theme: Option<TrayTheme>,
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+) -> Result<tauri::ipc::Response, String> {
+    let theme = theme.unwrap_or(config.read().await.get().tray_theme);
+    let (_, state) = tray::current_state(&app).await;
+    let icon = tray::icon::render(&state, tray::resolve_theme(&app, theme));
+    Ok(tauri::ipc::Response::new(icon.to_png()))
+}
+
+/// Send a digest right away; returns its Markdown version
+#[tauri::command]
+pub async fn send_digest_now(app: AppHandle) -> Result<String, String> {
+    digest::send_digest(&app)
+        .await
+        .map(|d| d.markdown)
+        .ok_or_else(|| "Digest is unavailable until the app has finished starting".to_string())
+}
+
+#[tauri::command]
+pub async fn pause_scheduler(scheduler: State<'_, Arc<Scheduler>>) -> Result<(), String> {
+    scheduler.pause().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_scheduler(scheduler: State<'_, Arc<Scheduler>>) -> Result<(), String> {
+    scheduler.resume().await;
+    Ok(())
+}
+
+/// Queue an immediate background refresh of one provider, or all enabled ones;
+/// results arrive as `provider-updated`/`provider-error` events
+#[tauri::command]
+pub async fn trigger_refresh(app: AppHandle, provider: Option<String>) -> Result<(), String> {
+    scheduler::refresh_now(&app, provider).await
+}
+
+#[tauri::command]
+pub async fn get_scheduler_status(
+    config: State<'_, Arc<RwLock<ConfigManager>>>,
+    registry: State<'_, Arc<RwLock<ProviderRegistry>>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<SchedulerStatus, String> {
+    // A provider locked for writing, e.g. by an auth flow, is left out rather than waited on
+    let min_intervals: Vec<_> = registry
+        .read()
+        .await
+        .enabled_providers()
+        .into_iter()
+        .filter_map(|(name, provider_arc)| {
+            let min_interval = provider_arc.try_read().ok()?.min_refresh_interval();
+            Some((name, min_interval))
+        })
+        .collect();
+    let thresholds = config.read().await.get().thresholds.clone();
+    Ok(scheduler.status(&min_intervals, &thresholds).await)
+}
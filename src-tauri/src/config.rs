@@ -0,0 +1,471 @@
+//! Persisted application configuration (`config.json` in the app data dir)
+//!
+//! Covers provider enablement and display order, the refresh interval, the
+//! usage warning thresholds and alert rules. Loaded once at startup and updated
+//! through commands.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::notifications::rules::{self, AlertRule};
+use crate::notifications::digest::DigestSettings;
+use crate::notifications::templates::TemplateSettings;
+use crate::tray::icon::TrayTheme;
+use crate::notifications::quiet::QuietHours;
+use crate::notifications::sinks::{self, SinkConfig};
+use crate::providers::ProviderRegistry;
+use crate::scheduler::{RefreshInterval, Scheduler};
+use crate::storage::atomic;
+use crate::storage::keyring::{KeyringError, RoutedStore};
+
+const CONFIG_FILE_NAME: &str = "config.json";
+/// 2: alert rules replace the fixed warning thresholds for notifications
+pub const CONFIG_VERSION: u32 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Invalid configuration: {0}")]
+    Invalid(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("Credential store error: {0}")]
+    Credentials(#[from] KeyringError),
+    #[error("Settings can't be saved: the config file couldn't be loaded and is left untouched")]
+    Unavailable,
+}
+
+pub type Result<T> = std::result::Result<T, ConfigError>;
+
+/// Per-provider settings; list position is the display order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub id: String,
+    #[serde(default)]
+    pub enabled: bool,
+    /// Overrides the global refresh interval for this provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_interval: Option<RefreshInterval>,
+    /// Notify when an exhausted or alerting quota comes back
+    #[serde(default = "default_notify_restored")]
+    pub notify_restored: bool,
+}
+
+fn default_notify_restored() -> bool {
+    true
+}
+
+/// Usage percentages considered "near the limit" (adaptive polling; seeds the default alert rules)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Thresholds {
+    #[serde(default = "default_session_warning")]
+    pub session_warning_percent: f64,
+    #[serde(default = "default_weekly_warning")]
+    pub weekly_warning_percent: f64,
+}
+
+fn default_session_warning() -> f64 {
+    80.0
+}
+
+fn default_weekly_warning() -> f64 {
+    90.0
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            session_warning_percent: default_session_warning(),
+            weekly_warning_percent: default_weekly_warning(),
+        }
+    }
+}
+
+/// Files written before the version field existed
+fn default_version() -> u32 {
+    1
+}
+
+fn default_refresh_interval() -> RefreshInterval {
+    RefreshInterval::FiveMinutes
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppConfig {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default = "default_refresh_interval")]
+    pub refresh_interval: RefreshInterval,
+    #[serde(default)]
+    pub providers: Vec<ProviderConfig>,
+    #[serde(default)]
+    pub thresholds: Thresholds,
+    /// Poll faster near limits/resets and back off while usage is unchanged
+    #[serde(default)]
+    pub adaptive_polling: bool,
+    /// Notification rules evaluated after every refresh
+    #[serde(default)]
+    pub alert_rules: Vec<AlertRule>,
+    /// External destinations (webhooks, chat, push) for notifications
+    #[serde(default)]
+    pub notification_sinks: Vec<SinkConfig>,
+    #[serde(default)]
+    pub quiet_hours: QuietHours,
+    /// Notify when consumption spikes far above its usual rate
+    #[serde(default = "default_spike_alerts")]
+    pub spike_alerts: bool,
+    #[serde(default)]
+    pub digest: DigestSettings,
+    /// Custom notification text and time formatting
+    #[serde(default)]
+    pub templates: TemplateSettings,
+    #[serde(default)]
+    pub tray_theme: TrayTheme,
+}
+
+fn default_spike_alerts() -> bool {
+    true
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            refresh_interval: default_refresh_interval(),
+            providers: Vec::new(),
+            thresholds: Thresholds::default(),
+            adaptive_polling: false,
+            alert_rules: rules::default_rules(&Thresholds::default()),
+            notification_sinks: Vec::new(),
+            quiet_hours: QuietHours::default(),
+            spike_alerts: default_spike_alerts(),
+            digest: DigestSettings::default(),
+            templates: TemplateSettings::default(),
+            tray_theme: TrayTheme::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Reject values the app can't act on
+    pub fn validate(&self, known_providers: &[String]) -> Result<()> {
+        for (name, value) in [
+            ("session_warning_percent", self.thresholds.session_warning_percent),
+            ("weekly_warning_percent", self.thresholds.weekly_warning_percent),
+        ] {
+            if !(value > 0.0 && value <= 100.0) {
+                return Err(ConfigError::Invalid(format!("{} must be between 0 and 100", name)));
+            }
+        }
+
+        let mut seen = Vec::new();
+        for provider in &self.providers {
+            if !known_providers.contains(&provider.id) {
+                return Err(ConfigError::Invalid(format!("unknown provider '{}'", provider.id)));
+            }
+            if seen.contains(&&provider.id) {
+                return Err(ConfigError::Invalid(format!("provider '{}' listed twice", provider.id)));
+            }
+            seen.push(&provider.id);
+        }
+
+        let mut ids = Vec::new();
+        for rule in &self.alert_rules {
+            rule.validate().map_err(ConfigError::Invalid)?;
+            if let Some(provider) = &rule.provider {
+                if !known_providers.contains(provider) {
+                    return Err(ConfigError::Invalid(format!("rule '{}': unknown provider '{}'", rule.id, provider)));
+                }
+            }
+            if ids.contains(&&rule.id) {
+                return Err(ConfigError::Invalid(format!("rule id '{}' used twice", rule.id)));
+            }
+            ids.push(&rule.id);
+        }
+
+        let mut ids = Vec::new();
+        for sink in &self.notification_sinks {
+            sink.validate().map_err(|e| ConfigError::Invalid(e.to_string()))?;
+            if ids.contains(&&sink.id) {
+                return Err(ConfigError::Invalid(format!("sink id '{}' used twice", sink.id)));
+            }
+            ids.push(&sink.id);
+        }
+
+        self.quiet_hours.validate().map_err(ConfigError::Invalid)?;
+        self.digest.validate().map_err(ConfigError::Invalid)?;
+        self.templates.validate().map_err(ConfigError::Invalid)?;
+        if let Some(id) = self.digest.sink_ids.iter().find(|id| !ids.contains(id)) {
+            return Err(ConfigError::Invalid(format!("digest sink '{}' is not configured", id)));
+        }
+        Ok(())
+    }
+
+    /// Repair a loaded config: drop unknown/duplicate providers, append missing ones,
+    /// reset out-of-range thresholds to their defaults, drop invalid alert rules and
+    /// seed rules from the thresholds for configs that predate them
+    pub fn normalize(&mut self, known_providers: &[String]) {
+        let mut seen: Vec<String> = Vec::new();
+        self.providers.retain(|p| {
+            let keep = known_providers.contains(&p.id) && !seen.contains(&p.id);
+            if !keep {
+                log::warn!("Dropping unknown or duplicate provider '{}' from config", p.id);
+            }
+            seen.push(p.id.clone());
+            keep
+        });
+
+        for id in known_providers {
+            if !self.providers.iter().any(|p| &p.id == id) {
+                self.providers.push(ProviderConfig {
+                    id: id.clone(),
+                    enabled: false,
+                    refresh_interval: None,
+                    notify_restored: default_notify_restored(),
+                });
+            }
+        }
+
+        let thresholds = &mut self.thresholds;
+        if !(thresholds.session_warning_percent > 0.0 && thresholds.session_warning_percent <= 100.0) {
+            thresholds.session_warning_percent = default_session_warning();
+        }
+        if !(thresholds.weekly_warning_percent > 0.0 && thresholds.weekly_warning_percent <= 100.0) {
+            thresholds.weekly_warning_percent = default_weekly_warning();
+        }
+
+        self.alert_rules.retain(|rule| match rule.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Dropping alert rule from config: {}", e);
+                false
+            }
+        });
+        if self.version < 2 && self.alert_rules.is_empty() {
+            self.alert_rules = rules::default_rules(&self.thresholds);
+        }
+        self.version = CONFIG_VERSION;
+    }
+
+    pub fn provider_order(&self) -> Vec<String> {
+        self.providers.iter().map(|p| p.id.clone()).collect()
+    }
+
+    /// Per-provider interval overrides, keyed by provider id
+    pub fn provider_intervals(&self) -> HashMap<String, RefreshInterval> {
+        self.providers
+            .iter()
+            .filter_map(|p| p.refresh_interval.map(|interval| (p.id.clone(), interval)))
+            .collect()
+    }
+
+    /// Unknown providers default to notifying
+    pub fn notify_restored(&self, id: &str) -> bool {
+        self.providers.iter().find(|p| p.id == id).is_none_or(|p| p.notify_restored)
+    }
+
+    pub fn set_provider_enabled(&mut self, id: &str, enabled: bool) {
+        if let Some(provider) = self.providers.iter_mut().find(|p| p.id == id) {
+            provider.enabled = enabled;
+        }
+    }
+}
+
+pub struct ConfigManager {
+    path: PathBuf,
+    config: AppConfig,
+    /// Credential keys of the sink secrets the file on disk refers to
+    sealed_sinks: Vec<String>,
+    /// The file on disk couldn't be loaded but may still be good; don't overwrite it
+    keep_file: bool,
+}
+
+impl ConfigManager {
+    /// Load `config.json`, falling back to defaults. Corrupt files are quarantined;
+    /// files that can't be read are left in place and never overwritten.
+    pub fn new(app_data_dir: PathBuf, known_providers: &[String]) -> Self {
+        let path = app_data_dir.join(CONFIG_FILE_NAME);
+
+        let mut keep_file = false;
+        let mut config = match atomic::load_json::<AppConfig>(&path, "config") {
+            atomic::Loaded::Found(config) => config,
+            atomic::Loaded::Kept => {
+                keep_file = true;
+                AppConfig::default()
+            }
+            atomic::Loaded::Missing | atomic::Loaded::Quarantined => AppConfig::default(),
+        };
+        config.normalize(known_providers);
+        let sealed_sinks = config.notification_sinks.iter().filter_map(|s| s.secret_key.clone()).collect();
+        sinks::unseal_secrets(&RoutedStore, &mut config.notification_sinks);
+
+        Self { path, config, sealed_sinks, keep_file }
+    }
+
+    pub fn get(&self) -> &AppConfig {
+        &self.config
+    }
+
+    /// Read back sink secrets that couldn't be read so far, e.g. from a locked vault
+    pub fn unseal_sinks(&mut self) {
+        sinks::unseal_secrets(&RoutedStore, &mut self.config.notification_sinks);
+    }
+
+    /// Validate, replace and persist the configuration
+    pub fn set(&mut self, config: AppConfig, known_providers: &[String]) -> Result<()> {
+        config.validate(known_providers)?;
+        let mut config = config;
+        config.normalize(known_providers);
+        // Restored backups refer to secrets imported alongside them
+        sinks::unseal_secrets(&RoutedStore, &mut config.notification_sinks);
+        self.config = config;
+        self.save()
+    }
+
+    /// Apply an in-place change and persist it
+    pub fn update(&mut self, f: impl FnOnce(&mut AppConfig)) -> Result<()> {
+        f(&mut self.config);
+        self.save()
+    }
+
+    /// Write `config.json`, moving sink secrets to the credential store
+    pub fn save(&mut self) -> Result<()> {
+        if self.keep_file {
+            return Err(ConfigError::Unavailable);
+        }
+        let mut config = self.config.clone();
+        self.sealed_sinks =
+            sinks::seal_secrets(&RoutedStore, &mut config.notification_sinks, &self.sealed_sinks)?;
+        let json = serde_json::to_string_pretty(&config)?;
+        atomic::write_atomic(&self.path, json)?;
+        Ok(())
+    }
+}
+
+/// Push a configuration into the live registry and scheduler
+pub async fn apply(config: &AppConfig, registry: &tokio::sync::RwLock<ProviderRegistry>, scheduler: &Scheduler) {
+    {
+        let mut registry = registry.write().await;
+        for provider in &config.providers {
+            registry.set_enabled(&provider.id, provider.enabled);
+        }
+        registry.set_order(config.provider_order());
+    }
+    scheduler.set_interval(config.refresh_interval).await;
+    scheduler.set_provider_intervals(config.provider_intervals()).await;
+    scheduler.set_adaptive(config.adaptive_polling).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn known() -> Vec<String> {
+        vec!["copilot".to_string(), "gemini".to_string()]
+    }
+
+    #[test]
+    fn test_normalize_repairs_loaded_config() {
+        let mut config: AppConfig = serde_json::from_str(
+            r#"{"refresh_interval":"15m","providers":[{"id":"gemini","enabled":true,"refresh_interval":"2m"},{"id":"bogus"}],"thresholds":{"session_warning_percent":150}}"#,
+        )
+        .unwrap();
+        config.normalize(&known());
+
+        assert_eq!(config.refresh_interval, RefreshInterval::FifteenMinutes);
+        assert_eq!(config.provider_order(), vec!["gemini".to_string(), "copilot".to_string()]);
+        assert!(config.providers[0].enabled);
+        assert_eq!(config.provider_intervals().get("gemini"), Some(&RefreshInterval::TwoMinutes));
+        assert_eq!(config.thresholds.session_warning_percent, 80.0);
+    }
+
+    #[test]
+    fn test_alert_rules_migration() {
+        // v1 configs get rules seeded from their thresholds
+        let mut config: AppConfig =
+            serde_json::from_str(r#"{"version":1,"thresholds":{"weekly_warning_percent":70}}"#).unwrap();
+        config.normalize(&known());
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.alert_rules, rules::default_rules(&config.thresholds));
+
+        // v2 configs keep an explicitly empty rule list
+        let mut config: AppConfig = serde_json::from_str(r#"{"version":2,"alert_rules":[]}"#).unwrap();
+        config.normalize(&known());
+        assert!(config.alert_rules.is_empty());
+
+        let mut config = AppConfig::default();
+        config.alert_rules.push(config.alert_rules[0].clone());
+        assert!(config.validate(&known()).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_input() {
+        let mut config = AppConfig::default();
+        config.normalize(&known());
+        assert!(config.validate(&known()).is_ok());
+
+        config.thresholds.weekly_warning_percent = 0.0;
+        assert!(config.validate(&known()).is_err());
+
+        let mut config = AppConfig::default();
+        config.providers.push(ProviderConfig {
+            id: "nope".into(),
+            enabled: true,
+            refresh_interval: None,
+            notify_restored: true,
+        });
+        assert!(config.validate(&known()).is_err());
+    }
+
+    #[test]
+    fn test_unreadable_config_is_kept() {
+        let dir = std::env::temp_dir().join(format!("limitswatcher-config-unreadable-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        // A directory in place of the file fails to read with something other than NotFound
+        let path = dir.join(CONFIG_FILE_NAME);
+        fs::create_dir_all(&path).unwrap();
+
+        let mut manager = ConfigManager::new(dir.clone(), &known());
+        assert_eq!(manager.get().refresh_interval, AppConfig::default().refresh_interval);
+        let result = manager.update(|config| config.adaptive_polling = !config.adaptive_polling);
+        assert!(matches!(result, Err(ConfigError::Unavailable)));
+        assert!(path.is_dir());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_sink_secrets_follow_the_vault() {
+        use crate::notifications::sinks::SinkTarget;
+        use crate::storage::{keyring::keys, vault};
+
+        let dir = std::env::temp_dir().join(format!("limitswatcher-config-vault-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        vault::init(&dir);
+        // What enable_vault leaves behind: the target sealed in the vault and a reference in config.json
+        let target = r#"{"type":"chat","url":"https://hooks.slack.com/services/T0/B0/secret","flavor":"slack"}"#;
+        vault::enable("pw".into(), HashMap::from([(keys::notification_sink("team"), target.to_string())]))
+            .await
+            .unwrap();
+        fs::write(
+            dir.join(CONFIG_FILE_NAME),
+            r#"{"notification_sinks":[{"id":"team","target":{"type":"chat","url":"","flavor":"slack"},"secret_key":"notification_sink.team"}]}"#,
+        )
+        .unwrap();
+
+        let manager = ConfigManager::new(dir.clone(), &known());
+        let sink = &manager.get().notification_sinks[0];
+        assert!(matches!(&sink.target, SinkTarget::Chat { url, .. } if url.contains("hooks.slack.com")));
+
+        // Locked at startup: redacted until the vault is unlocked
+        vault::with_vault(|v| v.lock()).unwrap();
+        let mut manager = ConfigManager::new(dir.clone(), &known());
+        assert!(manager.get().notification_sinks[0].secret_key.is_some());
+        vault::unlock("pw".into()).await.unwrap();
+        manager.unseal_sinks();
+        assert!(manager.get().notification_sinks[0].secret_key.is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
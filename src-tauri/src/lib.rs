@@ -1,4 +1,6 @@
 mod commands;
+mod config;
+mod monitor;
 mod notifications;
 mod providers;
 mod scheduler;
@@ -21,23 +23,61 @@ pub fn run() {
         .setup(|app| {
             // Initialize State
             let app_data_dir = app.path().app_data_dir().expect("failed to get app data dir");
-            let cache_manager = storage::CacheManager::new(app_data_dir);
+            storage::credentials::init(&app_data_dir);
+            storage::vault::init(&app_data_dir);
+            let cache_manager = storage::CacheManager::new(app_data_dir.clone());
             app.manage(Arc::new(RwLock::new(cache_manager)));
 
-            let provider_registry = providers::ProviderRegistry::new();
+            let mut provider_registry = providers::ProviderRegistry::new();
+            let config_manager = config::ConfigManager::new(app_data_dir.clone(), &provider_registry.all_provider_names());
+            let app_config = config_manager.get().clone();
+            for provider in &app_config.providers {
+                provider_registry.set_enabled(&provider.id, provider.enabled);
+            }
+            provider_registry.set_order(app_config.provider_order());
             app.manage(Arc::new(RwLock::new(provider_registry)));
+            app.manage(Arc::new(RwLock::new(config_manager)));
 
-            let notification_tracker = notifications::NotificationTracker::new();
+            let notification_tracker = notifications::NotificationTracker::load(&app_data_dir);
             app.manage(Arc::new(RwLock::new(notification_tracker)));
+            app.manage(Arc::new(RwLock::new(notifications::quiet::DoNotDisturb::default())));
+            app.manage(Arc::new(RwLock::new(notifications::anomaly::AnomalyDetector::default())));
+            app.manage(Arc::new(RwLock::new(notifications::digest::DigestState::load(&app_data_dir))));
+
+            app.manage(Arc::new(scheduler::Scheduler::from_config(&app_config)));
 
             // Initialize system tray
             tray::init(app)?;
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                tray::refresh(&handle).await;
+            });
 
             // Start background scheduler
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 scheduler::start(handle).await;
             });
+            
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                scheduler::run_vault_auto_lock(handle).await;
+            });
+            
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                monitor::run(handle).await;
+            });
+            
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                notifications::run_quiet_hours(handle).await;
+            });
+            
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                notifications::digest::run_digests(handle).await;
+            });
 
             // Hide dock icon on macOS (menu bar app style)
             #[cfg(target_os = "macos")]
@@ -55,6 +95,46 @@ pub fn run() {
             commands::complete_provider_auth,
             commands::logout_provider,
             commands::get_provider_auth_status,
+            commands::get_vault_status,
+            commands::enable_vault,
+            commands::disable_vault,
+            commands::unlock_vault,
+            commands::lock_vault,
+            commands::change_vault_passphrase,
+            commands::set_vault_auto_lock,
+            commands::get_credential_backend,
+            commands::set_credential_backend,
+            commands::recover_credentials,
+            commands::export_backup,
+            commands::import_backup,
+            commands::get_config,
+            commands::set_config,
+            commands::get_refresh_interval,
+            commands::set_refresh_interval,
+            commands::get_alert_rules,
+            commands::set_alert_rules,
+            commands::get_notification_sinks,
+            commands::set_notification_sinks,
+            commands::test_notification_sink,
+            commands::get_quiet_hours,
+            commands::set_quiet_hours,
+            commands::get_dnd_status,
+            commands::snooze_notifications,
+            commands::cancel_snooze,
+            commands::get_digest_settings,
+            commands::set_digest_settings,
+            commands::send_digest_now,
+            commands::get_notification_templates,
+            commands::get_default_notification_templates,
+            commands::set_notification_templates,
+            commands::get_tray_theme,
+            commands::set_tray_theme,
+            commands::get_tray_icon_png,
+            commands::pause_scheduler,
+            commands::resume_scheduler,
+            commands::trigger_refresh,
+            commands::get_scheduler_status,
+            commands::get_provider_health,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,182 @@
+//! System resume and network change detection
+//!
+//! Resume is detected from wall-clock jumps: the monotonic clock (and so
+//! `tokio::time::sleep`) stalls during suspend while the wall clock keeps going.
+//! Network changes are polled: on Linux from interface state and the default
+//! route in `/sys` and `/proc`, elsewhere with a DNS reachability probe.
+//! Either event marks the usage cache stale and refreshes every provider.
+
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::RwLock;
+
+use crate::scheduler;
+use crate::storage::CacheManager;
+use crate::tray;
+
+/// How often the monitor wakes
+const MONITOR_TICK: Duration = Duration::from_secs(10);
+
+/// Unaccounted time beyond this counts as a suspend/resume
+const CLOCK_JUMP_THRESHOLD: Duration = Duration::from_secs(60);
+
+/// Network state is sampled every this many ticks on platforms without cheap local state
+#[cfg(not(target_os = "linux"))]
+const NETWORK_PROBE_TICKS: u32 = 3;
+
+#[cfg(not(target_os = "linux"))]
+const NETWORK_PROBE_HOST: &str = "api.github.com:443";
+
+#[cfg(not(target_os = "linux"))]
+const NETWORK_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NetworkState {
+    online: bool,
+    /// Changes whenever the active connection does
+    fingerprint: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "snake_case", tag = "reason")]
+pub enum WakeReason {
+    Resumed { slept_secs: u64 },
+    NetworkChanged { online: bool },
+}
+
+/// Watch for resume and connectivity changes for the life of the app
+pub async fn run<R: Runtime>(app: AppHandle<R>) {
+    let mut last_wall = Utc::now();
+    let mut last_mono = Instant::now();
+    let mut network = network_state().await;
+    #[cfg(not(target_os = "linux"))]
+    let mut tick: u32 = 0;
+
+    loop {
+        tokio::time::sleep(MONITOR_TICK).await;
+
+        let now = Utc::now();
+        if let Some(slept) = clock_jump(last_wall, now, last_mono.elapsed()) {
+            log::info!("System resumed after ~{}s", slept.as_secs());
+            on_wake(&app, WakeReason::Resumed { slept_secs: slept.as_secs() }).await;
+        }
+        last_wall = now;
+        last_mono = Instant::now();
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            tick = tick.wrapping_add(1);
+            if tick % NETWORK_PROBE_TICKS != 0 {
+                continue;
+            }
+        }
+
+        let current = network_state().await;
+        if current != network {
+            let online = current.as_ref().is_some_and(|s| s.online);
+            log::info!("Network changed (online: {})", online);
+            if online {
+                on_wake(&app, WakeReason::NetworkChanged { online }).await;
+            } else {
+                let _ = app.emit("network-changed", WakeReason::NetworkChanged { online });
+            }
+            network = current;
+        }
+    }
+}
+
+/// Mark cached data stale and refresh everything now
+async fn on_wake<R: Runtime>(app: &AppHandle<R>, reason: WakeReason) {
+    if let Some(cache) = app.try_state::<Arc<RwLock<CacheManager>>>() {
+        cache.write().await.mark_stale();
+    }
+    tray::refresh(app).await;
+    let event = match reason {
+        WakeReason::Resumed { .. } => "system-resumed",
+        WakeReason::NetworkChanged { .. } => "network-changed",
+    };
+    let _ = app.emit(event, &reason);
+    if let Err(e) = scheduler::refresh_now(app, None).await {
+        log::warn!("Refresh after wake failed to start: {}", e);
+    }
+}
+
+/// Time the wall clock moved beyond what the monotonic clock saw, if it looks like a suspend
+fn clock_jump(last_wall: DateTime<Utc>, now: DateTime<Utc>, mono_elapsed: Duration) -> Option<Duration> {
+    let wall_elapsed = (now - last_wall).to_std().unwrap_or_default();
+    // A late tick also counts, for platforms whose monotonic clock includes suspend
+    let unaccounted = wall_elapsed
+        .saturating_sub(mono_elapsed)
+        .max(mono_elapsed.saturating_sub(MONITOR_TICK));
+    (unaccounted > CLOCK_JUMP_THRESHOLD).then_some(unaccounted)
+}
+
+#[cfg(target_os = "linux")]
+async fn network_state() -> Option<NetworkState> {
+    let mut up = Vec::new();
+    for entry in std::fs::read_dir("/sys/class/net").ok()?.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "lo" {
+            continue;
+        }
+        let state = std::fs::read_to_string(entry.path().join("operstate")).unwrap_or_default();
+        if state.trim() == "up" {
+            up.push(name);
+        }
+    }
+    up.sort();
+
+    let gateway = std::fs::read_to_string("/proc/net/route")
+        .ok()
+        .and_then(|routes| default_gateway(&routes));
+    Some(NetworkState {
+        online: gateway.is_some(),
+        fingerprint: format!("{}|{}", up.join(","), gateway.unwrap_or_default()),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn network_state() -> Option<NetworkState> {
+    let lookup = tokio::time::timeout(NETWORK_PROBE_TIMEOUT, tokio::net::lookup_host(NETWORK_PROBE_HOST)).await;
+    let online = matches!(lookup, Ok(Ok(_)));
+    Some(NetworkState {
+        online,
+        fingerprint: online.to_string(),
+    })
+}
+
+/// Interface and gateway of the default route in `/proc/net/route`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn default_gateway(routes: &str) -> Option<String> {
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [iface, "00000000", gateway, ..] => Some(format!("{}:{}", iface, gateway)),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_jump_and_default_route() {
+        let then = Utc::now();
+        let on_time = then + chrono::Duration::seconds(10);
+        assert_eq!(clock_jump(then, on_time, Duration::from_secs(10)), None);
+
+        let after_suspend = then + chrono::Duration::minutes(30);
+        let slept = clock_jump(then, after_suspend, Duration::from_secs(10)).unwrap();
+        assert_eq!(slept.as_secs(), 30 * 60 - 10);
+
+        let routes = "Iface\tDestination\tGateway\tFlags\n\
+                      wlan0\t0010A8C0\t00000000\t0001\n\
+                      wlan0\t00000000\t0100A8C0\t0003\n";
+        assert_eq!(default_gateway(routes).as_deref(), Some("wlan0:0100A8C0"));
+        assert_eq!(default_gateway("Iface\tDestination\tGateway\n"), None);
+    }
+}
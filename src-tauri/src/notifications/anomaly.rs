@@ -0,0 +1,185 @@
+//! Usage spike detection
+//!
+//! Each refresh yields a consumption rate (percentage points per hour) per
+//! window. A rate far above the window's recent baseline, judged by rolling
+//! median and MAD, is reported as a spike with a projected time to exhaustion.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+use super::rules::{measures, AlertWindow};
+use crate::storage::UsageData;
+
+/// Rates kept per window for the baseline
+const BASELINE_LEN: usize = 48;
+
+/// Rates needed before spikes are reported
+const MIN_BASELINE: usize = 6;
+
+/// Refreshes closer together than this are merged into the next sample
+const MIN_SAMPLE_GAP: Duration = Duration::minutes(5);
+
+/// How many (normal-consistent) MADs above the median counts as a spike
+const MAD_MULTIPLIER: f64 = 4.0;
+
+/// Scales MAD to a standard deviation for normally distributed rates
+const MAD_SCALE: f64 = 1.4826;
+
+/// Rates below this many percentage points per hour never count as spikes
+const MIN_SPIKE_RATE: f64 = 10.0;
+
+/// Quiet period per window after a spike is reported
+const SPIKE_COOLDOWN: Duration = Duration::hours(1);
+
+/// Consumption well above a window's baseline
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSpike {
+    pub provider: String,
+    pub window: AlertWindow,
+    pub model: Option<String>,
+    /// Percentage points per hour since the previous sample
+    pub rate_per_hour: f64,
+    /// Median rate over the baseline
+    pub baseline_per_hour: f64,
+    /// Rate in the provider's units (requests, messages), when the limit is known
+    pub units_per_hour: Option<f64>,
+    pub percent: f64,
+    /// When the window runs out at this rate, if before its reset
+    pub exhausts_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct WindowHistory {
+    last: Option<(DateTime<Utc>, f64)>,
+    rates: VecDeque<f64>,
+    last_spike: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct AnomalyDetector {
+    /// Keyed by `provider:window[:model]`
+    windows: HashMap<String, WindowHistory>,
+}
+
+impl AnomalyDetector {
+    /// Record a usage snapshot and return any windows whose consumption spiked
+    pub fn observe(&mut self, provider: &str, usage: &UsageData, now: DateTime<Utc>) -> Vec<UsageSpike> {
+        let mut spikes = Vec::new();
+        for measure in measures(usage) {
+            let Some(percent) = measure.percent else {
+                continue;
+            };
+            let key = match &measure.model {
+                Some(model) => format!("{}:{}:{}", provider, measure.window.as_str(), model),
+                None => format!("{}:{}", provider, measure.window.as_str()),
+            };
+            let history = self.windows.entry(key).or_default();
+
+            let Some((then, before)) = history.last else {
+                history.last = Some((now, percent));
+                continue;
+            };
+            if now - then < MIN_SAMPLE_GAP {
+                continue;
+            }
+            history.last = Some((now, percent));
+            if percent < before {
+                // The window reset; the baseline still describes normal use
+                continue;
+            }
+
+            let hours = (now - then).num_seconds() as f64 / 3600.0;
+            let rate = (percent - before) / hours;
+            let baseline = history.baseline(rate, now);
+
+            if history.rates.len() == BASELINE_LEN {
+                history.rates.pop_front();
+            }
+            history.rates.push_back(rate);
+
+            let Some(baseline) = baseline else {
+                continue;
+            };
+            history.last_spike = Some(now);
+
+            let exhausts_at = (percent < 100.0)
+                .then(|| now + Duration::seconds(((100.0 - percent) / rate * 3600.0) as i64))
+                .filter(|at| measure.reset_at.is_none_or(|reset| *at < reset));
+            spikes.push(UsageSpike {
+                provider: provider.to_string(),
+                window: measure.window,
+                model: measure.model,
+                rate_per_hour: rate,
+                baseline_per_hour: baseline,
+                units_per_hour: measure.limit.map(|limit| rate / 100.0 * limit as f64),
+                percent,
+                exhausts_at,
+            });
+        }
+        spikes
+    }
+}
+
+impl WindowHistory {
+    /// The baseline median if `rate` is a spike against it
+    fn baseline(&self, rate: f64, now: DateTime<Utc>) -> Option<f64> {
+        if self.rates.len() < MIN_BASELINE || self.last_spike.is_some_and(|at| now - at < SPIKE_COOLDOWN) {
+            return None;
+        }
+        let mut rates: Vec<f64> = self.rates.iter().copied().collect();
+        let typical = median(&mut rates);
+        let mut deviations: Vec<f64> = rates.iter().map(|r| (r - typical).abs()).collect();
+        let mad = median(&mut deviations);
+        let threshold = typical + (MAD_MULTIPLIER * MAD_SCALE * mad).max(MIN_SPIKE_RATE);
+        (rate > threshold).then_some(typical)
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spike_against_baseline() {
+        let mut detector = AnomalyDetector::default();
+        let start = Utc::now();
+        let usage = |used: u64| UsageData {
+            weekly_used: used,
+            weekly_limit: 1000,
+            weekly_reset_time: Some(start + Duration::days(5)),
+            ..Default::default()
+        };
+
+        // A steady 1% per hour builds the baseline
+        for hour in 0..=8 {
+            let spikes = detector.observe("claude", &usage(hour * 10), start + Duration::hours(hour as i64));
+            assert!(spikes.is_empty());
+        }
+
+        // 30% in the next hour
+        let now = start + Duration::hours(9);
+        let spikes = detector.observe("claude", &usage(380), now);
+        assert_eq!(spikes.len(), 1);
+        let spike = &spikes[0];
+        assert_eq!(spike.window, AlertWindow::Weekly);
+        assert!((spike.rate_per_hour - 30.0).abs() < 1e-6);
+        assert!((spike.baseline_per_hour - 1.0).abs() < 1e-6);
+        assert_eq!(spike.units_per_hour.map(|u| u.round()), Some(300.0));
+        let exhausts_in = spike.exhausts_at.unwrap() - now;
+        assert!((exhausts_in.num_minutes() - 124).abs() <= 1);
+
+        // Still climbing, but within the cooldown
+        assert!(detector.observe("claude", &usage(600), now + Duration::minutes(30)).is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+//! Alerts for credentials that stopped working or are about to expire
+//!
+//! One notification per incident: a failure alert is sent once and re-armed
+//! when auth succeeds again; an expiry warning is sent once per expiry time.
+
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::sync::RwLock;
+
+use super::rules::Severity;
+use super::sinks::Notification;
+use super::{notify, NotificationTracker};
+use crate::providers::{ProviderError, ProviderRegistry};
+
+/// Warn this long before credentials that can't be renewed expire
+const EXPIRY_WARNING: Duration = Duration::hours(24);
+
+fn failure_scope(provider: &str) -> String {
+    format!("auth:{}", provider)
+}
+
+fn expiry_scope(provider: &str) -> String {
+    format!("expiry:{}", provider)
+}
+
+/// Record `key` as sent; returns false if it already was
+async fn first_time<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &str,
+    scope: &str,
+    expires_at: Option<DateTime<Utc>>,
+) -> bool {
+    let Some(tracker) = app.try_state::<Arc<RwLock<NotificationTracker>>>() else {
+        return true;
+    };
+    let mut tracker = tracker.write().await;
+    if tracker.was_sent(key, Utc::now()) {
+        return false;
+    }
+    tracker.mark_sent(key, scope, expires_at);
+    tracker.save();
+    true
+}
+
+/// Provider display name, falling back to its id while it is busy
+async fn display_name<R: Runtime>(app: &AppHandle<R>, provider: &str) -> String {
+    let provider_arc = match app.try_state::<Arc<RwLock<ProviderRegistry>>>() {
+        Some(registry) => registry.read().await.get_provider(provider),
+        None => None,
+    };
+    provider_arc
+        .and_then(|p| p.try_read().ok().map(|p| p.info().name))
+        .unwrap_or_else(|| provider.to_string())
+}
+
+/// Notify once when a refresh fails because the user has to sign in again
+pub async fn check_auth_failure<R: Runtime>(app: &AppHandle<R>, provider: &str, error: &ProviderError) {
+    if !error.is_auth_failure() {
+        return;
+    }
+    let scope = failure_scope(provider);
+    if !first_time(app, &scope, &scope, None).await {
+        return;
+    }
+    let name = display_name(app, provider).await;
+    let title = format!("Re-authenticate {}", name);
+    let body = format!("{} rejected the stored credentials ({}). Sign in again to resume tracking.", name, error);
+    notify(app, Notification::new("auth_failed", Severity::Critical, Some(provider), &title, &body)).await;
+}
+
+/// Warn once when credentials that can't be renewed are close to expiring
+pub async fn check_credential_expiry<R: Runtime>(
+    app: &AppHandle<R>,
+    provider: &str,
+    expires_at: Option<DateTime<Utc>>,
+) {
+    let Some(expires_at) = expires_at else {
+        return;
+    };
+    let remaining = expires_at - Utc::now();
+    if remaining <= Duration::zero() || remaining > EXPIRY_WARNING {
+        return;
+    }
+    let key = format!("{}:{}", expiry_scope(provider), expires_at.timestamp());
+    if !first_time(app, &key, &expiry_scope(provider), Some(expires_at)).await {
+        return;
+    }
+    let name = display_name(app, provider).await;
+    let title = format!("{} sign-in expiring", name);
+    let body = format!(
+        "{} credentials expire in {}h {}m. Re-authenticate to keep tracking usage.",
+        name,
+        remaining.num_hours(),
+        remaining.num_minutes() % 60
+    );
+    notify(app, Notification::new("credentials_expiring", Severity::Warning, Some(provider), &title, &body)).await;
+}
+
+/// Re-arm the failure alert after auth works again
+pub async fn clear_auth_failure<R: Runtime>(app: &AppHandle<R>, provider: &str) {
+    let Some(tracker) = app.try_state::<Arc<RwLock<NotificationTracker>>>() else {
+        return;
+    };
+    let mut tracker = tracker.write().await;
+    if tracker.clear_scope(&failure_scope(provider)) {
+        tracker.save();
+    }
+}
@@ -0,0 +1,443 @@
+//! Scheduled usage digests
+//!
+//! A daily or weekly summary of every enabled provider: current usage, what was
+//! consumed since the previous digest, upcoming resets and providers in error.
+//! Shown natively; selected sinks get a longer Markdown version.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::sync::RwLock;
+
+use super::rules::Severity;
+use super::sinks::{self, Notification};
+use crate::config::ConfigManager;
+use crate::providers::ProviderRegistry;
+use crate::scheduler::Scheduler;
+use crate::storage::{atomic, CacheManager, UsageData};
+
+const DIGEST_STATE_FILE_NAME: &str = "digest_state.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    Off,
+    Daily,
+    Weekly,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestSettings {
+    #[serde(default = "default_frequency")]
+    pub frequency: DigestFrequency,
+    /// Local time of day, `HH:MM`
+    #[serde(default = "default_time")]
+    pub time: String,
+    /// Day weekly digests go out on
+    #[serde(default = "default_weekday")]
+    pub weekday: Weekday,
+    /// Sinks that receive the Markdown version
+    #[serde(default)]
+    pub sink_ids: Vec<String>,
+}
+
+fn default_frequency() -> DigestFrequency {
+    DigestFrequency::Off
+}
+
+fn default_time() -> String {
+    "09:00".to_string()
+}
+
+fn default_weekday() -> Weekday {
+    Weekday::Mon
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            frequency: default_frequency(),
+            time: default_time(),
+            weekday: default_weekday(),
+            sink_ids: Vec::new(),
+        }
+    }
+}
+
+impl DigestSettings {
+    fn time_of_day(&self) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(&self.time, "%H:%M").ok()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.time_of_day() {
+            Some(_) => Ok(()),
+            None => Err(format!("digest time '{}' must be HH:MM", self.time)),
+        }
+    }
+
+    /// The latest scheduled digest time at or before `now`
+    pub fn last_due(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let time = self.time_of_day()?;
+        let local_now = now.with_timezone(&Local);
+        let today = local_now.date_naive();
+        let at = |day: chrono::NaiveDate| Local.from_local_datetime(&day.and_time(time)).earliest();
+
+        let mut day = match self.frequency {
+            DigestFrequency::Off => return None,
+            DigestFrequency::Daily => today,
+            DigestFrequency::Weekly => {
+                let back = (7 + today.weekday().num_days_from_monday() - self.weekday.num_days_from_monday()) % 7;
+                today - Duration::days(back as i64)
+            }
+        };
+        let step = match self.frequency {
+            DigestFrequency::Weekly => Duration::days(7),
+            _ => Duration::days(1),
+        };
+        // Today's (or this week's) slot may still be ahead
+        if at(day).is_none_or(|due| due > local_now) {
+            day -= step;
+        }
+        at(day).map(|due| due.with_timezone(&Utc))
+    }
+}
+
+/// When the last digest went out and the usage it reported, for "since last digest"
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DigestState {
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    pub last_sent: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub usage: HashMap<String, UsageData>,
+}
+
+impl DigestState {
+    pub fn load(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join(DIGEST_STATE_FILE_NAME);
+        // A file that can't be read is left in place and the state kept in memory only
+        match atomic::load_json::<DigestState>(&path, "digest state") {
+            atomic::Loaded::Found(state) => Self { path: Some(path), ..state },
+            atomic::Loaded::Missing | atomic::Loaded::Quarantined => Self { path: Some(path), ..Self::default() },
+            atomic::Loaded::Kept => Self::default(),
+        }
+    }
+
+    /// Whether a digest is due, starting the schedule on first use instead of sending at once
+    pub fn is_due(&mut self, settings: &DigestSettings, now: DateTime<Utc>) -> bool {
+        let Some(due) = settings.last_due(now) else {
+            return false;
+        };
+        match self.last_sent {
+            Some(last) => last < due,
+            None => {
+                // Persisted so restarts before the first slot don't start over
+                self.last_sent = Some(now);
+                self.save();
+                false
+            }
+        }
+    }
+
+    pub fn record(&mut self, now: DateTime<Utc>, usage: HashMap<String, UsageData>) {
+        self.last_sent = Some(now);
+        self.usage = usage;
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else { return };
+        let result = serde_json::to_string_pretty(self)
+            .map_err(std::io::Error::from)
+            .and_then(|json| atomic::write_atomic(path, json));
+        if let Err(e) = result {
+            log::warn!("Failed to save digest state: {}", e);
+        }
+    }
+}
+
+/// One provider's line in a digest
+#[derive(Debug, Clone)]
+pub struct DigestEntry {
+    pub name: String,
+    pub usage: Option<UsageData>,
+    /// Usage reported by the previous digest
+    pub previous: Option<UsageData>,
+    pub error: Option<String>,
+}
+
+pub struct Digest {
+    pub title: String,
+    pub body: String,
+    pub markdown: String,
+}
+
+fn percent(used: u64, limit: u64) -> Option<f64> {
+    (limit > 0).then(|| used as f64 / limit as f64 * 100.0)
+}
+
+/// `45%` plus the change since the previous digest, e.g. `45% (+12)`
+fn with_change(now: Option<f64>, before: Option<f64>) -> Option<String> {
+    let now = now?;
+    Some(match before {
+        Some(before) if now >= before && (now - before).round() > 0.0 => format!("{:.0}% (+{:.0})", now, now - before),
+        _ => format!("{:.0}%", now),
+    })
+}
+
+fn usage_parts(entry: &DigestEntry) -> Vec<String> {
+    let Some(usage) = &entry.usage else {
+        return Vec::new();
+    };
+    let previous = entry.previous.as_ref();
+    let mut parts = Vec::new();
+    if let Some(text) = with_change(
+        percent(usage.session_used, usage.session_limit),
+        previous.and_then(|p| percent(p.session_used, p.session_limit)),
+    ) {
+        parts.push(format!("session {}", text));
+    }
+    if let Some(text) = with_change(
+        percent(usage.weekly_used, usage.weekly_limit),
+        previous.and_then(|p| percent(p.weekly_used, p.weekly_limit)),
+    ) {
+        parts.push(format!("weekly {}", text));
+    }
+    for quota in usage.model_quotas.iter().flatten() {
+        let before = previous
+            .and_then(|p| p.model_quotas.as_ref())
+            .and_then(|quotas| quotas.iter().find(|q| q.model_id == quota.model_id))
+            .map(|q| 100.0 - q.percent_left);
+        if let Some(text) = with_change(Some(100.0 - quota.percent_left), before) {
+            parts.push(format!("{} {}", quota.model_id, text));
+        }
+    }
+    if let Some(credits) = usage.credits_remaining {
+        parts.push(format!("{} credits left", credits));
+    }
+    parts
+}
+
+fn next_reset(usage: &UsageData, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    usage
+        .reset_time
+        .into_iter()
+        .chain(usage.weekly_reset_time)
+        .chain(usage.model_quotas.iter().flatten().filter_map(|q| q.reset_time))
+        .filter(|at| *at > now)
+        .min()
+}
+
+pub fn build(frequency: DigestFrequency, entries: &[DigestEntry], now: DateTime<Utc>) -> Digest {
+    let period = match frequency {
+        DigestFrequency::Weekly => "Weekly",
+        _ => "Daily",
+    };
+    let title = format!("{} usage digest", period);
+
+    let mut lines = Vec::new();
+    let mut markdown = vec![format!("## {}", title), String::new()];
+    for entry in entries {
+        let parts = usage_parts(entry);
+        let reset = entry.usage.as_ref().and_then(|u| next_reset(u, now));
+
+        let mut line = match &entry.error {
+            Some(_) => format!("⚠ {}: error", entry.name),
+            None if parts.is_empty() => format!("{}: no data", entry.name),
+            None => format!("{}: {}", entry.name, parts.join(", ")),
+        };
+        if let (None, Some(reset)) = (&entry.error, reset) {
+            line.push_str(&format!("; resets {}", reset.with_timezone(&Local).format("%a %H:%M")));
+        }
+        lines.push(line);
+
+        markdown.push(format!("### {}", entry.name));
+        if parts.is_empty() {
+            markdown.push("- No usage data".to_string());
+        }
+        markdown.extend(parts.iter().map(|p| format!("- {}", p)));
+        if let Some(reset) = reset {
+            markdown.push(format!("- Next reset: {}", reset.with_timezone(&Local).format("%a %b %e, %H:%M")));
+        }
+        if let Some(error) = &entry.error {
+            markdown.push(format!("- **Error:** {}", error));
+        }
+        markdown.push(String::new());
+    }
+    if entries.is_empty() {
+        lines.push("No providers enabled".to_string());
+        markdown.push("_No providers enabled._".to_string());
+    }
+
+    Digest {
+        title,
+        body: lines.join("\n"),
+        markdown: markdown.join("\n").trim_end().to_string(),
+    }
+}
+
+/// Build and send a digest of every enabled provider now
+pub async fn send_digest<R: Runtime>(app: &AppHandle<R>) -> Option<Digest> {
+    let config = app.try_state::<Arc<RwLock<ConfigManager>>>()?;
+    let (settings, sink_configs) = {
+        let config = config.read().await;
+        let config = config.get();
+        (config.digest.clone(), config.notification_sinks.clone())
+    };
+    let providers = app.try_state::<Arc<RwLock<ProviderRegistry>>>()?.read().await.enabled_providers();
+    let cached = app.try_state::<Arc<RwLock<CacheManager>>>()?.read().await.get_all().clone();
+    let state = app.try_state::<Arc<RwLock<DigestState>>>()?;
+    let scheduler = app.try_state::<Arc<Scheduler>>();
+
+    let mut entries = Vec::new();
+    let mut reported = HashMap::new();
+    {
+        let state = state.read().await;
+        for (id, provider) in providers {
+            let name = provider.try_read().map(|p| p.info().name).unwrap_or_else(|_| id.clone());
+            let usage = cached.get(&id).cloned();
+            let failing = match &scheduler {
+                Some(scheduler) => scheduler
+                    .health(&id)
+                    .await
+                    .filter(|h| h.consecutive_failures > 0)
+                    .and_then(|h| h.recent_errors.first().map(|e| e.message.clone())),
+                None => None,
+            };
+            let error = failing.or_else(|| usage.as_ref().and_then(|u| u.error.clone()));
+            if let Some(usage) = &usage {
+                reported.insert(id.clone(), usage.clone());
+            }
+            entries.push(DigestEntry {
+                name,
+                usage,
+                previous: state.usage.get(&id).cloned(),
+                error,
+            });
+        }
+    }
+
+    let now = Utc::now();
+    let digest = build(settings.frequency, &entries, now);
+    let notification = Notification::new("digest", Severity::Info, None, &digest.title, &digest.body)
+        .with_markdown(digest.markdown.clone());
+    // Quiet hours hold the popup; the digest's own sinks still get it on schedule
+    super::show(app, &notification).await;
+    for sink in sink_configs.into_iter().filter(|s| s.enabled && settings.sink_ids.contains(&s.id)) {
+        let notification = notification.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = sinks::deliver(sink.build().as_ref(), &notification).await {
+                log::warn!("Failed to deliver digest to sink '{}': {}", sink.id, e);
+            }
+        });
+    }
+
+    state.write().await.record(now, reported);
+    Some(digest)
+}
+
+/// Send digests on their schedule for the life of the app
+pub async fn run_digests<R: Runtime>(app: AppHandle<R>) {
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+
+        let settings = match app.try_state::<Arc<RwLock<ConfigManager>>>() {
+            Some(config) => config.read().await.get().digest.clone(),
+            None => continue,
+        };
+        let due = match app.try_state::<Arc<RwLock<DigestState>>>() {
+            Some(state) => state.write().await.is_due(&settings, Utc::now()),
+            None => false,
+        };
+        if due {
+            send_digest(&app).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_build_lists_usage_changes_and_errors() {
+        let now = Utc::now();
+        let entries = vec![
+            DigestEntry {
+                name: "Claude".into(),
+                usage: Some(UsageData {
+                    session_used: 45,
+                    session_limit: 100,
+                    weekly_used: 60,
+                    weekly_limit: 100,
+                    weekly_reset_time: Some(now + Duration::days(2)),
+                    ..Default::default()
+                }),
+                previous: Some(UsageData {
+                    weekly_used: 48,
+                    weekly_limit: 100,
+                    ..Default::default()
+                }),
+                error: None,
+            },
+            DigestEntry {
+                name: "Gemini".into(),
+                usage: None,
+                previous: None,
+                error: Some("Token expired".into()),
+            },
+        ];
+
+        let digest = build(DigestFrequency::Daily, &entries, now);
+        assert_eq!(digest.title, "Daily usage digest");
+        assert!(digest.body.starts_with("Claude: session 45%, weekly 60% (+12); resets "));
+        assert!(digest.body.ends_with("⚠ Gemini: error"));
+        assert!(digest.markdown.contains("### Gemini\n- No usage data\n- **Error:** Token expired"));
+    }
+
+    #[test]
+    fn test_schedule_starts_on_first_use() {
+        let settings = DigestSettings {
+            frequency: DigestFrequency::Daily,
+            ..Default::default()
+        };
+        let now = Utc::now();
+        let due = settings.last_due(now).unwrap();
+        assert!(due <= now && now - due < Duration::days(1));
+
+        let dir = std::env::temp_dir().join(format!("limitswatcher-digest-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut state = DigestState::load(&dir);
+        assert!(!state.is_due(&settings, now));
+        assert!(state.is_due(&settings, now + Duration::days(1)));
+
+        // The start survives a restart instead of being pushed back each launch
+        let mut reloaded = DigestState::load(&dir);
+        assert_eq!(reloaded.last_sent, Some(now));
+        assert!(reloaded.is_due(&settings, now + Duration::days(1)));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let off = DigestSettings::default();
+        assert!(off.last_due(now).is_none());
+    }
+
+    #[test]
+    fn test_unreadable_state_is_kept() {
+        let dir = std::env::temp_dir().join(format!("limitswatcher-digest-unreadable-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        // A directory in place of the file fails to read with something other than NotFound
+        let path = dir.join(DIGEST_STATE_FILE_NAME);
+        fs::create_dir_all(&path).unwrap();
+
+        let mut state = DigestState::load(&dir);
+        state.record(Utc::now(), HashMap::new());
+        assert!(path.is_dir());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
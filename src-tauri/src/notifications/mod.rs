@@ -0,0 +1,252 @@
+//! Native notification system for usage alerts
+//!
+//! Alerts are shown natively and forwarded to any configured external sinks,
+//! unless quiet hours or a snooze hold them for a later summary.
+
+pub mod anomaly;
+pub mod auth;
+pub mod digest;
+pub mod quiet;
+pub mod rules;
+pub mod sinks;
+pub mod templates;
+pub mod tracker;
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter, Runtime, Manager};
+use tauri_plugin_notification::NotificationExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use crate::config::ConfigManager;
+use crate::storage::UsageData;
+use anomaly::AnomalyDetector;
+use quiet::{DoNotDisturb, QuietHours};
+use rules::{Alert, Severity};
+use sinks::Notification;
+use templates::TemplateSettings;
+pub use tracker::NotificationTracker;
+
+/// How often held notifications are checked for release
+const QUIET_HOURS_CHECK: Duration = Duration::from_secs(60);
+
+/// Send a warning notification
+pub async fn send_warning<R: Runtime>(app: &AppHandle<R>, title: &str, body: &str) {
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+    {
+        log::error!("Failed to send notification: {}", e);
+    }
+}
+
+/// Send an error notification
+pub async fn send_error<R: Runtime>(app: &AppHandle<R>, title: &str, body: &str) {
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(&format!("❌ {}", title))
+        .body(body)
+        .show()
+    {
+        log::error!("Failed to send notification: {}", e);
+    }
+}
+
+/// Send an info notification
+pub async fn send_info<R: Runtime>(app: &AppHandle<R>, title: &str, body: &str) {
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+    {
+        log::error!("Failed to send notification: {}", e);
+    }
+}
+
+/// Evaluate the configured alert rules against a provider's fresh usage and notify,
+/// including quotas restored since the `previous` snapshot
+pub async fn check_usage_alerts<R: Runtime>(
+    app: &AppHandle<R>,
+    provider: &str,
+    previous: Option<&UsageData>,
+    usage: &UsageData,
+) {
+    let Some(config) = app.try_state::<Arc<RwLock<ConfigManager>>>() else {
+        return;
+    };
+    let (alerts, restored, templates) = {
+        let config = config.read().await;
+        let config = config.get();
+        let restored = match previous {
+            Some(previous) if config.notify_restored(provider) => {
+                rules::restored(&config.alert_rules, provider, previous, usage)
+            }
+            _ => Vec::new(),
+        };
+        (rules::evaluate(&config.alert_rules, provider, usage), restored, config.templates.clone())
+    };
+
+    for restored in restored {
+        notify(app, templates.restored(&restored)).await;
+    }
+
+    let alerts = unsent(app, provider, alerts).await;
+    for alert in alerts {
+        notify(app, templates.alert(&alert, Utc::now())).await;
+    }
+
+    check_usage_spikes(app, provider, usage).await;
+}
+
+/// Feed the spike detector and report consumption far above the usual rate
+async fn check_usage_spikes<R: Runtime>(app: &AppHandle<R>, provider: &str, usage: &UsageData) {
+    let Some(detector) = app.try_state::<Arc<RwLock<AnomalyDetector>>>() else {
+        return;
+    };
+    let spikes = detector.write().await.observe(provider, usage, Utc::now());
+    if spikes.is_empty() {
+        return;
+    }
+    let (notify_spikes, templates) = match app.try_state::<Arc<RwLock<ConfigManager>>>() {
+        Some(config) => {
+            let config = config.read().await;
+            (config.get().spike_alerts, config.get().templates.clone())
+        }
+        None => (true, TemplateSettings::default()),
+    };
+
+    for spike in spikes {
+        log::warn!("{} usage spike: {:.1}%/h ({:?})", provider, spike.rate_per_hour, spike.window);
+        let _ = app.emit("usage-spike", &spike);
+        if notify_spikes {
+            notify(app, templates.spike(&spike, Utc::now())).await;
+        }
+    }
+}
+
+/// Show a notification and forward it to sinks, or hold it during quiet hours
+pub async fn notify<R: Runtime>(app: &AppHandle<R>, notification: Notification) {
+    if !hold_if_quiet(app, &notification).await {
+        deliver_now(app, notification).await;
+    }
+}
+
+/// Show a notification without forwarding it to sinks, or hold it during quiet
+/// hours; for callers that deliver to their own choice of sinks
+pub async fn show<R: Runtime>(app: &AppHandle<R>, notification: &Notification) {
+    if !hold_if_quiet(app, notification).await {
+        show_native(app, notification).await;
+    }
+}
+
+async fn hold_if_quiet<R: Runtime>(app: &AppHandle<R>, notification: &Notification) -> bool {
+    let held = match app.try_state::<Arc<RwLock<DoNotDisturb>>>() {
+        Some(dnd) => {
+            let hours = quiet_hours(app).await;
+            dnd.write().await.hold_if_quiet(&hours, notification, Utc::now())
+        }
+        None => false,
+    };
+    if held {
+        log::debug!("Holding notification during quiet hours: {}", notification.title);
+    }
+    held
+}
+
+async fn deliver_now<R: Runtime>(app: &AppHandle<R>, notification: Notification) {
+    show_native(app, &notification).await;
+    dispatch(app, notification).await;
+}
+
+async fn show_native<R: Runtime>(app: &AppHandle<R>, notification: &Notification) {
+    let (title, body) = (&notification.title, &notification.body);
+    match notification.severity {
+        Severity::Info => send_info(app, title, body).await,
+        Severity::Warning => send_warning(app, title, body).await,
+        Severity::Critical => send_error(app, title, body).await,
+    }
+}
+
+async fn quiet_hours<R: Runtime>(app: &AppHandle<R>) -> QuietHours {
+    match app.try_state::<Arc<RwLock<ConfigManager>>>() {
+        Some(config) => config.read().await.get().quiet_hours.clone(),
+        None => QuietHours::default(),
+    }
+}
+
+/// Deliver one summary of held notifications if quiet time has ended
+pub async fn release_held<R: Runtime>(app: &AppHandle<R>) {
+    let Some(dnd) = app.try_state::<Arc<RwLock<DoNotDisturb>>>() else {
+        return;
+    };
+    let hours = quiet_hours(app).await;
+    let summary = dnd.write().await.take_summary(&hours, Utc::now());
+    if let Some(summary) = summary {
+        deliver_now(app, summary).await;
+    }
+}
+
+/// Release held notifications when quiet hours or a snooze end
+pub async fn run_quiet_hours<R: Runtime>(app: AppHandle<R>) {
+    loop {
+        tokio::time::sleep(QUIET_HOURS_CHECK).await;
+        release_held(&app).await;
+    }
+}
+
+/// Forward a notification to every configured sink that accepts its severity
+pub async fn dispatch<R: Runtime>(app: &AppHandle<R>, notification: Notification) {
+    let Some(config) = app.try_state::<Arc<RwLock<ConfigManager>>>() else {
+        return;
+    };
+    let targets: Vec<_> = config
+        .read()
+        .await
+        .get()
+        .notification_sinks
+        .iter()
+        .filter(|sink| sink.accepts(notification.severity))
+        .cloned()
+        .collect();
+
+    // Deliver in the background so slow or retrying sinks don't hold up refreshes
+    for sink in targets {
+        let notification = notification.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = sinks::deliver(sink.build().as_ref(), &notification).await {
+                log::warn!("Failed to deliver notification to sink '{}': {}", sink.id, e);
+            }
+        });
+    }
+}
+
+/// Keep alerts not yet sent in their reset period and record them as sent
+async fn unsent<R: Runtime>(app: &AppHandle<R>, provider: &str, alerts: Vec<Alert>) -> Vec<Alert> {
+    let Some(tracker) = app.try_state::<Arc<RwLock<NotificationTracker>>>() else {
+        return alerts;
+    };
+    let mut tracker = tracker.write().await;
+    let now = Utc::now();
+    let active: Vec<String> = alerts.iter().map(Alert::scope).collect();
+    let mut changed = tracker.prune(now) | tracker.clear_inactive(provider, &active);
+
+    let alerts: Vec<Alert> = alerts
+        .into_iter()
+        .filter(|alert| !tracker.was_sent(&alert.dedup_key(), now))
+        .collect();
+    for alert in &alerts {
+        tracker.mark_sent(&alert.dedup_key(), &alert.scope(), alert.reset_at);
+        changed = true;
+    }
+    if changed {
+        tracker.save();
+    }
+    alerts
+}
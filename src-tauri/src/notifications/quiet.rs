@@ -0,0 +1,290 @@
+//! Quiet hours and manual snooze
+//!
+//! While either is active, notifications are held instead of shown and
+//! delivered as one summary once quiet time ends. Critical ones can be allowed
+//! through.
+//!
+//! The snooze and held notifications live in memory only: quitting the app
+//! ends the snooze and drops anything not yet summarized.
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use super::rules::Severity;
+use super::sinks::Notification;
+
+/// Held notifications listed individually in the summary
+const SUMMARY_MAX_LINES: usize = 5;
+
+/// A recurring quiet window, e.g. 22:00-07:00 on weekdays
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuietPeriod {
+    /// Days the window starts on; empty means every day
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// `HH:MM`; a start after the end spans midnight
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietPeriod {
+    fn times(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let start = NaiveTime::parse_from_str(&self.start, "%H:%M").ok()?;
+        let end = NaiveTime::parse_from_str(&self.end, "%H:%M").ok()?;
+        Some((start, end))
+    }
+
+    fn on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn contains(&self, at: NaiveDateTime) -> bool {
+        let Some((start, end)) = self.times() else {
+            return false;
+        };
+        let (day, time) = (at.weekday(), at.time());
+        if start <= end {
+            self.on(day) && time >= start && time < end
+        } else {
+            (self.on(day) && time >= start) || (self.on(day.pred()) && time < end)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuietHours {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `None` or `local` for the system timezone, otherwise an IANA name such as
+    /// `Europe/Berlin`, `UTC` or a fixed offset such as `+02:00`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub periods: Vec<QuietPeriod>,
+    /// Show critical notifications even while quiet
+    #[serde(default = "default_critical_breaks_through")]
+    pub critical_breaks_through: bool,
+}
+
+fn default_critical_breaks_through() -> bool {
+    true
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timezone: None,
+            periods: Vec::new(),
+            critical_breaks_through: default_critical_breaks_through(),
+        }
+    }
+}
+
+enum Zone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+fn parse_zone(timezone: &str) -> Option<Zone> {
+    match timezone {
+        "utc" | "Z" => Some(Zone::Named(Tz::UTC)),
+        name => match name.parse() {
+            Ok(tz) => Some(Zone::Named(tz)),
+            Err(_) => name.parse().ok().map(Zone::Fixed),
+        },
+    }
+}
+
+impl QuietHours {
+    /// Wall-clock time in the configured timezone
+    fn local_time(&self, now: DateTime<Utc>) -> NaiveDateTime {
+        match self.timezone.as_deref().filter(|tz| *tz != "local").and_then(parse_zone) {
+            Some(Zone::Named(tz)) => now.with_timezone(&tz).naive_local(),
+            Some(Zone::Fixed(offset)) => now.with_timezone(&offset).naive_local(),
+            None => now.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let at = self.local_time(now);
+        self.periods.iter().any(|p| p.contains(at))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(tz) = self.timezone.as_deref().filter(|tz| *tz != "local") {
+            if parse_zone(tz).is_none() {
+                return Err(format!(
+                    "unsupported timezone '{}'; use local, an IANA name like Europe/Berlin, UTC or an offset like +02:00",
+                    tz
+                ));
+            }
+        }
+        for period in &self.periods {
+            if period.times().is_none() {
+                return Err(format!("quiet period {}-{} must use HH:MM times", period.start, period.end));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Snooze state and notifications held while quiet
+#[derive(Debug, Default)]
+pub struct DoNotDisturb {
+    snoozed_until: Option<DateTime<Utc>>,
+    held: Vec<Notification>,
+}
+
+/// Do-not-disturb state as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct DndStatus {
+    pub quiet: bool,
+    pub snoozed_until: Option<String>,
+    pub held: usize,
+}
+
+impl DoNotDisturb {
+    pub fn snooze(&mut self, until: DateTime<Utc>) {
+        self.snoozed_until = Some(until);
+    }
+
+    pub fn cancel_snooze(&mut self) {
+        self.snoozed_until = None;
+    }
+
+    pub fn snoozed_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.snoozed_until.filter(|until| *until > now)
+    }
+
+    pub fn is_quiet(&self, hours: &QuietHours, now: DateTime<Utc>) -> bool {
+        self.snoozed_until(now).is_some() || hours.is_active(now)
+    }
+
+    /// Hold `notification` if quiet time applies to it; returns whether it was held
+    pub fn hold_if_quiet(&mut self, hours: &QuietHours, notification: &Notification, now: DateTime<Utc>) -> bool {
+        if !self.is_quiet(hours, now) {
+            return false;
+        }
+        if notification.severity == Severity::Critical && hours.critical_breaks_through {
+            return false;
+        }
+        self.held.push(notification.clone());
+        true
+    }
+
+    /// One notification summarizing everything held, once quiet time is over
+    pub fn take_summary(&mut self, hours: &QuietHours, now: DateTime<Utc>) -> Option<Notification> {
+        if self.held.is_empty() || self.is_quiet(hours, now) {
+            return None;
+        }
+        summarize(std::mem::take(&mut self.held))
+    }
+
+    pub fn status(&self, hours: &QuietHours, now: DateTime<Utc>) -> DndStatus {
+        DndStatus {
+            quiet: self.is_quiet(hours, now),
+            snoozed_until: self.snoozed_until(now).map(|t| t.to_rfc3339()),
+            held: self.held.len(),
+        }
+    }
+}
+
+fn summarize(held: Vec<Notification>) -> Option<Notification> {
+    let severity = held.iter().map(|n| n.severity).max()?;
+    let provider = held[0].provider.clone().filter(|p| held.iter().all(|n| n.provider.as_ref() == Some(p)));
+
+    let mut lines: Vec<String> = held
+        .iter()
+        .take(SUMMARY_MAX_LINES)
+        .map(|n| format!("{}: {}", n.title, n.body))
+        .collect();
+    if held.len() > SUMMARY_MAX_LINES {
+        lines.push(format!("…and {} more", held.len() - SUMMARY_MAX_LINES));
+    }
+
+    let title = match held.len() {
+        1 => "1 notification during quiet hours".to_string(),
+        n => format!("{} notifications during quiet hours", n),
+    };
+    Some(Notification::new(
+        "quiet_hours_summary",
+        severity,
+        provider.as_deref(),
+        &title,
+        &lines.join("\n"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    fn nights() -> QuietHours {
+        QuietHours {
+            enabled: true,
+            timezone: Some("+02:00".into()),
+            periods: vec![QuietPeriod {
+                days: vec![Weekday::Fri],
+                start: "22:00".into(),
+                end: "07:00".into(),
+            }],
+            critical_breaks_through: true,
+        }
+    }
+
+    #[test]
+    fn test_quiet_period_spans_midnight_in_timezone() {
+        let hours = nights();
+        // 2026-10-16 is a Friday; 20:30Z is 22:30 at +02:00
+        assert!(hours.is_active(at("2026-10-16T20:30:00Z")));
+        assert!(!hours.is_active(at("2026-10-16T19:30:00Z")));
+        // Saturday 06:00 local, still inside Friday's window
+        assert!(hours.is_active(at("2026-10-17T04:00:00Z")));
+        assert!(!hours.is_active(at("2026-10-17T05:30:00Z")));
+        // Thursday night is not configured
+        assert!(!hours.is_active(at("2026-10-15T20:30:00Z")));
+        assert!(hours.validate().is_ok());
+
+        // Named zones follow daylight saving: 22:30 in Berlin is 20:30Z in
+        // summer and 21:30Z in winter (2026-11-06 is a Friday)
+        let berlin = QuietHours { timezone: Some("Europe/Berlin".into()), ..nights() };
+        assert!(berlin.is_active(at("2026-10-16T20:30:00Z")));
+        assert!(!berlin.is_active(at("2026-11-06T20:30:00Z")));
+        assert!(berlin.is_active(at("2026-11-06T21:30:00Z")));
+        assert!(berlin.validate().is_ok());
+        assert!(QuietHours { timezone: Some("Mars/Olympus".into()), ..nights() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_held_notifications_are_summarized_after_quiet_time() {
+        let hours = nights();
+        let mut dnd = DoNotDisturb::default();
+        let night = at("2026-10-16T21:00:00Z");
+        let warning = Notification::new("usage_alert", Severity::Warning, Some("claude"), "claude Weekly Limit", "Weekly usage at 91%");
+        let critical = Notification::new("usage_alert", Severity::Critical, Some("claude"), "claude Usage Warning", "Session usage at 100%");
+
+        assert!(dnd.hold_if_quiet(&hours, &warning, night));
+        assert!(!dnd.hold_if_quiet(&hours, &critical, night));
+        assert!(dnd.take_summary(&hours, night).is_none());
+
+        let summary = dnd.take_summary(&hours, at("2026-10-17T06:00:00Z")).unwrap();
+        assert_eq!(summary.title, "1 notification during quiet hours");
+        assert_eq!(summary.provider.as_deref(), Some("claude"));
+        assert_eq!(dnd.status(&hours, night).held, 0);
+
+        // Snooze applies outside the schedule too
+        let day = at("2026-10-17T12:00:00Z");
+        dnd.snooze(day + chrono::Duration::hours(2));
+        assert!(dnd.hold_if_quiet(&hours, &warning, day));
+        assert!(!dnd.is_quiet(&hours, day + chrono::Duration::hours(3)));
+    }
+}
@@ -0,0 +1,452 @@
+//! Alert threshold rules evaluated against fresh usage data
+//!
+//! A rule watches one window (session, weekly, a model quota or credits) of one
+//! provider or all of them, and fires at a usage percentage or when the remaining
+//! amount drops below a value. Several rules on the same window form a ladder
+//! (e.g. 50/80/95%); only the highest one that matches fires per refresh.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::Thresholds;
+use crate::storage::UsageData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertWindow {
+    Session,
+    Weekly,
+    /// Per-model quotas (`UsageData::model_quotas`)
+    Model,
+    Credits,
+}
+
+impl AlertWindow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertWindow::Session => "session",
+            AlertWindow::Weekly => "weekly",
+            AlertWindow::Model => "model",
+            AlertWindow::Credits => "credits",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Usage reached this percentage of the limit
+    UsagePercent { percent: f64 },
+    /// Remaining amount dropped below this value (credits, requests, or percent left for models)
+    RemainingBelow { value: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Provider id; `None` applies to every provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub window: AlertWindow,
+    /// Model id or glob (`*`, `?`; case-insensitive) for `AlertWindow::Model`,
+    /// e.g. `gemini-*-pro*`; `None` matches every model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub condition: AlertCondition,
+    pub severity: Severity,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A rule that matched the latest usage
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub rule_id: String,
+    pub provider: String,
+    pub window: AlertWindow,
+    pub model: Option<String>,
+    pub severity: Severity,
+    /// Usage percentage of the window, when it has a limit
+    pub percent: Option<f64>,
+    pub used: Option<u64>,
+    pub limit: Option<u64>,
+    pub remaining: Option<f64>,
+    /// When the window resets, if the provider reports it
+    pub reset_at: Option<DateTime<Utc>>,
+}
+
+impl Alert {
+    /// `provider:window[:model]`
+    pub fn scope(&self) -> String {
+        match &self.model {
+            Some(model) => format!("{}:{}:{}", self.provider, self.window.as_str(), model),
+            None => format!("{}:{}", self.provider, self.window.as_str()),
+        }
+    }
+
+    /// Identifies this alert within its reset period, for deduplication
+    pub fn dedup_key(&self) -> String {
+        let period = self.reset_at.map_or_else(|| "-".to_string(), |at| at.timestamp().to_string());
+        format!("{}:{}:{}", self.scope(), self.rule_id, period)
+    }
+}
+
+impl AlertRule {
+    fn usage_percent(id: &str, window: AlertWindow, percent: f64, severity: Severity) -> Self {
+        Self {
+            id: id.to_string(),
+            enabled: true,
+            provider: None,
+            window,
+            model: None,
+            condition: AlertCondition::UsagePercent { percent },
+            severity,
+        }
+    }
+
+    fn applies_to(&self, provider: &str, model: Option<&str>) -> bool {
+        self.enabled
+            && self.provider.as_deref().is_none_or(|p| p == provider)
+            && match (&self.model, model) {
+                (Some(pattern), Some(model)) => glob_match(pattern, model),
+                _ => true,
+            }
+    }
+
+    /// How far up the ladder this rule sits, for picking one alert per window
+    fn rank(&self) -> (Severity, f64) {
+        let level = match self.condition {
+            AlertCondition::UsagePercent { percent } => percent,
+            // Lower "remaining" thresholds are the more urgent ones
+            AlertCondition::RemainingBelow { value } => -value,
+        };
+        (self.severity, level)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("rule id must not be empty".into());
+        }
+        match self.condition {
+            AlertCondition::UsagePercent { percent } if !(percent > 0.0 && percent <= 100.0) => {
+                Err(format!("rule '{}': percent must be between 0 and 100", self.id))
+            }
+            AlertCondition::UsagePercent { .. } if self.window == AlertWindow::Credits => {
+                Err(format!("rule '{}': credits have no limit; use remaining_below", self.id))
+            }
+            AlertCondition::RemainingBelow { value } if value.is_nan() || value < 0.0 => {
+                Err(format!("rule '{}': value must not be negative", self.id))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Match `name` against a pattern where `*` is any run of characters and `?` any one
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name index it is currently matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((after_star, matched)) => {
+                    p = after_star;
+                    n = matched + 1;
+                    backtrack = Some((after_star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Rules matching the app's behavior before rules existed, seeded from the legacy thresholds,
+/// plus a warning for Pro-tier model quotas
+pub fn default_rules(thresholds: &Thresholds) -> Vec<AlertRule> {
+    let mut pro_models =
+        AlertRule::usage_percent("pro-model-warning", AlertWindow::Model, thresholds.session_warning_percent, Severity::Warning);
+    pro_models.model = Some("*pro*".to_string());
+    vec![
+        AlertRule::usage_percent("session-warning", AlertWindow::Session, thresholds.session_warning_percent, Severity::Warning),
+        AlertRule::usage_percent("weekly-warning", AlertWindow::Weekly, thresholds.weekly_warning_percent, Severity::Warning),
+        pro_models,
+    ]
+}
+
+/// A window that was exhausted or alerting and has come back
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Restored {
+    pub provider: String,
+    pub window: AlertWindow,
+    pub model: Option<String>,
+    pub percent: Option<f64>,
+    pub remaining: Option<f64>,
+}
+
+/// One measurable window of a usage snapshot
+pub(super) struct Measure {
+    pub window: AlertWindow,
+    pub model: Option<String>,
+    pub percent: Option<f64>,
+    pub used: Option<u64>,
+    pub limit: Option<u64>,
+    pub remaining: Option<f64>,
+    pub reset_at: Option<DateTime<Utc>>,
+}
+
+impl Measure {
+    fn is_depleted(&self) -> bool {
+        self.remaining.is_some_and(|r| r <= 0.0)
+    }
+
+    fn same_window(&self, window: AlertWindow, model: &Option<String>) -> bool {
+        self.window == window && &self.model == model
+    }
+}
+
+pub(super) fn measures(usage: &UsageData) -> Vec<Measure> {
+    let mut out = Vec::new();
+    for (window, used, limit, reset_at) in [
+        (AlertWindow::Session, usage.session_used, usage.session_limit, usage.reset_time),
+        (AlertWindow::Weekly, usage.weekly_used, usage.weekly_limit, usage.weekly_reset_time),
+    ] {
+        if limit > 0 {
+            out.push(Measure {
+                window,
+                model: None,
+                percent: Some(used as f64 / limit as f64 * 100.0),
+                used: Some(used),
+                limit: Some(limit),
+                remaining: Some(limit.saturating_sub(used) as f64),
+                reset_at,
+            });
+        }
+    }
+    for quota in usage.model_quotas.iter().flatten() {
+        out.push(Measure {
+            window: AlertWindow::Model,
+            model: Some(quota.model_id.clone()),
+            percent: Some(100.0 - quota.percent_left),
+            used: None,
+            limit: None,
+            remaining: Some(quota.percent_left),
+            reset_at: quota.reset_time,
+        });
+    }
+    if let Some(credits) = usage.credits_remaining {
+        out.push(Measure {
+            window: AlertWindow::Credits,
+            model: None,
+            percent: None,
+            used: None,
+            limit: None,
+            remaining: Some(credits as f64),
+            reset_at: None,
+        });
+    }
+    out
+}
+
+/// Alerts for a provider's latest usage, at most one per window/model
+pub fn evaluate(rules: &[AlertRule], provider: &str, usage: &UsageData) -> Vec<Alert> {
+    let mut fired: HashMap<(AlertWindow, Option<String>), &AlertRule> = HashMap::new();
+    let measures = measures(usage);
+
+    for measure in &measures {
+        for rule in rules.iter().filter(|r| r.window == measure.window) {
+            if !rule.applies_to(provider, measure.model.as_deref()) {
+                continue;
+            }
+            let matched = match rule.condition {
+                AlertCondition::UsagePercent { percent } => measure.percent.is_some_and(|p| p >= percent),
+                AlertCondition::RemainingBelow { value } => measure.remaining.is_some_and(|r| r < value),
+            };
+            if !matched {
+                continue;
+            }
+            let key = (measure.window, measure.model.clone());
+            let replace = fired.get(&key).is_none_or(|current| rule.rank() > current.rank());
+            if replace {
+                fired.insert(key, rule);
+            }
+        }
+    }
+
+    measures
+        .into_iter()
+        .filter_map(|m| {
+            let rule = fired.get(&(m.window, m.model.clone()))?;
+            Some(Alert {
+                rule_id: rule.id.clone(),
+                provider: provider.to_string(),
+                window: m.window,
+                model: m.model,
+                severity: rule.severity,
+                percent: m.percent,
+                used: m.used,
+                limit: m.limit,
+                remaining: m.remaining,
+                reset_at: m.reset_at,
+            })
+        })
+        .collect()
+}
+
+/// Windows that went from depleted or alerting to neither between two snapshots
+pub fn restored(rules: &[AlertRule], provider: &str, previous: &UsageData, current: &UsageData) -> Vec<Restored> {
+    let alerting_before = evaluate(rules, provider, previous);
+    let alerting_now = evaluate(rules, provider, current);
+    let before = measures(previous);
+
+    measures(current)
+        .into_iter()
+        .filter(|now| {
+            let Some(prev) = before.iter().find(|p| p.same_window(now.window, &now.model)) else {
+                return false;
+            };
+            let was_hot = prev.is_depleted()
+                || alerting_before.iter().any(|a| a.window == now.window && a.model == now.model);
+            let still_hot = now.is_depleted()
+                || alerting_now.iter().any(|a| a.window == now.window && a.model == now.model);
+            let recovered = matches!((prev.remaining, now.remaining), (Some(p), Some(n)) if n > p);
+            was_hot && !still_hot && recovered
+        })
+        .map(|m| Restored {
+            provider: provider.to_string(),
+            window: m.window,
+            model: m.model,
+            percent: m.percent,
+            remaining: m.remaining,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ModelQuota;
+
+    #[test]
+    fn test_evaluate_picks_highest_rule_per_window() {
+        let mut rules = vec![
+            AlertRule::usage_percent("s50", AlertWindow::Session, 50.0, Severity::Info),
+            AlertRule::usage_percent("s80", AlertWindow::Session, 80.0, Severity::Warning),
+            AlertRule::usage_percent("s95", AlertWindow::Session, 95.0, Severity::Critical),
+            AlertRule::usage_percent("pro", AlertWindow::Model, 90.0, Severity::Warning),
+            AlertRule {
+                id: "credits".into(),
+                enabled: true,
+                provider: Some("claude".into()),
+                window: AlertWindow::Credits,
+                model: None,
+                condition: AlertCondition::RemainingBelow { value: 5.0 },
+                severity: Severity::Warning,
+            },
+        ];
+        rules[3].model = Some("Gemini-*PRO".into());
+
+        let usage = UsageData {
+            session_used: 85,
+            session_limit: 100,
+            credits_remaining: Some(3),
+            model_quotas: Some(vec![
+                ModelQuota { model_id: "gemini-pro".into(), percent_left: 5.0, reset_time: None },
+                ModelQuota { model_id: "gemini-flash".into(), percent_left: 5.0, reset_time: None },
+            ]),
+            ..Default::default()
+        };
+
+        let alerts = evaluate(&rules, "gemini", &usage);
+        let fired: Vec<_> = alerts.iter().map(|a| a.rule_id.as_str()).collect();
+        assert_eq!(fired, vec!["s80", "pro"]);
+
+        let alerts = evaluate(&rules, "claude", &usage);
+        assert!(alerts.iter().any(|a| a.rule_id == "credits"));
+
+        rules[1].enabled = false;
+        assert_eq!(evaluate(&rules, "gemini", &usage)[0].rule_id, "s50");
+    }
+
+    #[test]
+    fn test_restored_after_depletion_or_alert() {
+        let rules = default_rules(&Thresholds::default());
+        let quota = |left: f64| ModelQuota { model_id: "gemini-pro".into(), percent_left: left, reset_time: None };
+        let before = UsageData {
+            session_used: 100,
+            session_limit: 100,
+            weekly_used: 50,
+            weekly_limit: 100,
+            model_quotas: Some(vec![quota(0.0)]),
+            ..Default::default()
+        };
+        let after = UsageData {
+            session_used: 3,
+            session_limit: 100,
+            weekly_used: 51,
+            weekly_limit: 100,
+            model_quotas: Some(vec![quota(100.0)]),
+            ..Default::default()
+        };
+
+        let restored = restored(&rules, "gemini", &before, &after);
+        let windows: Vec<_> = restored.iter().map(|r| (r.window, r.model.as_deref())).collect();
+        assert_eq!(windows, vec![(AlertWindow::Session, None), (AlertWindow::Model, Some("gemini-pro"))]);
+        assert!(super::restored(&rules, "gemini", &after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("gemini-*-pro*", "gemini-2.5-pro-preview"));
+        assert!(glob_match("*flash", "gemini-2.0-flash"));
+        assert!(glob_match("gemini-?.5-pro", "gemini-2.5-pro"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("*pro", "gemini-2.5-flash"));
+        assert!(!glob_match("gemini-pro", "gemini-pro-vision"));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_rules() {
+        let mut rule = AlertRule::usage_percent("x", AlertWindow::Session, 120.0, Severity::Info);
+        assert!(rule.validate().is_err());
+        rule.condition = AlertCondition::UsagePercent { percent: 50.0 };
+        assert!(rule.validate().is_ok());
+        rule.window = AlertWindow::Credits;
+        assert!(rule.validate().is_err());
+    }
+}
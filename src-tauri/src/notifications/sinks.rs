@@ -0,0 +1,666 @@
+//! External notification sinks: webhooks, chat and push services
+//!
+//! Each configured sink receives the notifications at or above its minimum
+//! severity. Payloads can be templated; transient failures (network errors,
+//! 429 and 5xx responses) are retried a few times with backoff. Secret URLs
+//! and tokens are kept in the credential store rather than `config.json`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::rules::Severity;
+use super::templates;
+use crate::storage::credentials::CredentialStore;
+use crate::storage::keyring::{self, keys};
+
+/// Attempts per delivery, including the first
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubled for each further one
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SinkError {
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("Sink returned HTTP {0}")]
+    Status(u16),
+    #[error("Invalid sink: {0}")]
+    Invalid(String),
+}
+
+impl SinkError {
+    fn is_transient(&self) -> bool {
+        match self {
+            SinkError::Network(_) => true,
+            SinkError::Status(code) => *code == 429 || *code >= 500,
+            SinkError::Invalid(_) => false,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, SinkError>;
+
+/// A notification as delivered to sinks
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// What triggered it, e.g. `usage_alert` or `quota_restored`
+    pub event: String,
+    pub severity: Severity,
+    pub provider: Option<String>,
+    pub title: String,
+    pub body: String,
+    /// Longer Markdown version for sinks that render it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markdown: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// Template placeholder values the title and body were rendered from
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub values: HashMap<String, String>,
+}
+
+impl Notification {
+    pub fn new(event: &str, severity: Severity, provider: Option<&str>, title: &str, body: &str) -> Self {
+        Self {
+            event: event.to_string(),
+            severity,
+            provider: provider.map(str::to_string),
+            title: title.to_string(),
+            body: body.to_string(),
+            markdown: None,
+            timestamp: Utc::now(),
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn with_values(mut self, values: HashMap<&str, String>) -> Self {
+        self.values = values
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        self
+    }
+
+    pub fn with_markdown(mut self, markdown: String) -> Self {
+        self.markdown = Some(markdown);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatFlavor {
+    Slack,
+    Discord,
+    Mattermost,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkTarget {
+    /// POSTs a JSON document; `template` overrides the default payload
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Incoming-webhook URL of a chat service
+    Chat { url: String, flavor: ChatFlavor },
+    /// ntfy topic URL, e.g. `https://ntfy.sh/my-topic`
+    Ntfy {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
+    /// Gotify server URL and application token
+    Gotify { url: String, token: String },
+}
+
+impl SinkTarget {
+    fn url(&self) -> &str {
+        match self {
+            SinkTarget::Webhook { url, .. }
+            | SinkTarget::Chat { url, .. }
+            | SinkTarget::Ntfy { url, .. }
+            | SinkTarget::Gotify { url, .. } => url,
+        }
+    }
+
+    /// Copy without secrets: chat and webhook URLs, webhook headers and push tokens
+    fn redacted(&self) -> SinkTarget {
+        match self {
+            SinkTarget::Webhook { .. } => SinkTarget::Webhook { url: String::new(), headers: HashMap::new() },
+            SinkTarget::Chat { flavor, .. } => SinkTarget::Chat { url: String::new(), flavor: *flavor },
+            SinkTarget::Ntfy { url, .. } => SinkTarget::Ntfy { url: url.clone(), token: None },
+            SinkTarget::Gotify { url, .. } => SinkTarget::Gotify { url: url.clone(), token: String::new() },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SinkConfig {
+    pub id: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Only notifications at or above this severity are sent
+    #[serde(default = "default_min_severity")]
+    pub min_severity: Severity,
+    pub target: SinkTarget,
+    /// Message text (chat/push) or JSON document (webhook). Placeholders are those of
+    /// notification templates plus `{title}`, `{body}`, `{event}` and `{timestamp}`;
+    /// `[optional]` segments work in text templates only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Credential key holding the full target when `target` is redacted: always
+    /// in the config file, and in memory when the entry couldn't be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_min_severity() -> Severity {
+    Severity::Warning
+}
+
+impl SinkConfig {
+    pub fn accepts(&self, severity: Severity) -> bool {
+        self.enabled && severity >= self.min_severity
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.id.trim().is_empty() {
+            return Err(SinkError::Invalid("sink id must not be empty".into()));
+        }
+        // A redacted target has no URL to check until its secrets are read back
+        if self.secret_key.is_none() {
+            let url = reqwest::Url::parse(self.target.url())
+                .map_err(|e| SinkError::Invalid(format!("sink '{}': {}", self.id, e)))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(SinkError::Invalid(format!("sink '{}': URL must be http or https", self.id)));
+            }
+        }
+        if let Some(template) = &self.template {
+            let unknown = templates::unknown_placeholders(template, true);
+            if !unknown.is_empty() {
+                return Err(SinkError::Invalid(format!(
+                    "sink '{}': unknown placeholder {{{}}}",
+                    self.id,
+                    unknown.join("}, {")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn build(&self) -> Box<dyn NotificationSink> {
+        let template = self.template.clone();
+        match &self.target {
+            SinkTarget::Webhook { url, headers } => Box::new(WebhookSink {
+                url: url.clone(),
+                headers: headers.clone(),
+                template,
+            }),
+            SinkTarget::Chat { url, flavor } => Box::new(ChatSink {
+                url: url.clone(),
+                flavor: *flavor,
+                template,
+            }),
+            SinkTarget::Ntfy { url, token } => Box::new(NtfySink {
+                url: url.clone(),
+                token: token.clone(),
+                template,
+            }),
+            SinkTarget::Gotify { url, token } => Box::new(GotifySink {
+                url: url.clone(),
+                token: token.clone(),
+                template,
+            }),
+        }
+    }
+}
+
+/// Move sink targets with secrets into `store` under per-sink keys, leaving a
+/// redacted target and a reference in `sinks`. `sealed` holds the keys returned
+/// by the previous call; entries no longer referenced are removed.
+pub fn seal_secrets(store: &dyn CredentialStore, sinks: &mut [SinkConfig], sealed: &[String]) -> keyring::Result<Vec<String>> {
+    let mut referenced = Vec::new();
+    for sink in sinks.iter_mut() {
+        let redacted = sink.target.redacted();
+        if sink.target != redacted {
+            let key = keys::notification_sink(&sink.id);
+            let target = serde_json::to_string(&sink.target)?;
+            // Most saves change something else; skip rewriting the keychain
+            if store.get(&key)?.as_deref() != Some(target.as_str()) {
+                store.set(&key, &target)?;
+            }
+            sink.target = redacted;
+            sink.secret_key = Some(key);
+        }
+        referenced.extend(sink.secret_key.clone());
+    }
+
+    for key in sealed.iter().filter(|key| !referenced.contains(key)) {
+        if let Err(e) = store.delete(key) {
+            log::warn!("Failed to remove secrets of deleted sink ({}): {}", key, e);
+        }
+    }
+    if referenced != sealed {
+        store.set(keys::NOTIFICATION_SINKS, &serde_json::to_string(&referenced)?)?;
+    }
+    Ok(referenced)
+}
+
+/// Redact `sinks` the way [`seal_secrets`] would, without touching the credential
+/// store: each target with secrets only keeps a reference to its key
+pub fn redact_secrets(sinks: &mut [SinkConfig]) {
+    for sink in sinks.iter_mut() {
+        let redacted = sink.target.redacted();
+        if sink.target != redacted {
+            sink.target = redacted;
+            sink.secret_key = Some(keys::notification_sink(&sink.id));
+        }
+    }
+}
+
+/// Restore targets moved out by [`seal_secrets`]; a sink whose entry can't be
+/// read keeps its redacted target and reference
+pub fn unseal_secrets(store: &dyn CredentialStore, sinks: &mut [SinkConfig]) {
+    for sink in sinks.iter_mut() {
+        let Some(key) = &sink.secret_key else {
+            continue;
+        };
+        let target = store
+            .get(key)
+            .and_then(|stored| Ok(stored.map(|json| serde_json::from_str::<SinkTarget>(&json)).transpose()?));
+        match target {
+            Ok(Some(target)) => {
+                sink.target = target;
+                sink.secret_key = None;
+            }
+            Ok(None) => log::warn!("Secrets of sink '{}' are missing from the credential store", sink.id),
+            Err(e) => log::warn!("Failed to read secrets of sink '{}': {}", sink.id, e),
+        }
+    }
+}
+
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    /// One delivery attempt
+    async fn send(&self, client: &reqwest::Client, notification: &Notification) -> Result<()>;
+}
+
+/// Send with retries on transient failures
+pub async fn deliver(sink: &dyn NotificationSink, notification: &Notification) -> Result<()> {
+    let client = client();
+    let mut delay = RETRY_BASE_DELAY;
+    let mut attempt = 1;
+    loop {
+        match sink.send(client, notification).await {
+            Err(e) if e.is_transient() && attempt < MAX_ATTEMPTS => {
+                log::debug!("Sink delivery attempt {} failed, retrying: {}", attempt, e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// Fill a sink template with the notification's fields and template values.
+/// `json` escapes values for a JSON document and treats brackets as JSON syntax
+/// rather than optional segments.
+pub fn render(template: &str, notification: &Notification, json: bool) -> String {
+    let timestamp = notification.timestamp.to_rfc3339();
+    let fields = [
+        ("title", notification.title.as_str()),
+        ("body", notification.body.as_str()),
+        ("provider", notification.provider.as_deref().unwrap_or("")),
+        ("severity", notification.severity.as_str()),
+        ("event", notification.event.as_str()),
+        ("timestamp", &timestamp),
+    ];
+    let values: HashMap<&str, String> = notification
+        .values
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .chain(fields)
+        .map(|(name, value)| (name, if json { json_escaped(value) } else { value.to_string() }))
+        .collect();
+    templates::render_with(template, &values, !json)
+}
+
+/// Escape a value for use inside a JSON string literal
+fn json_escaped(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+async fn check(response: std::result::Result<reqwest::Response, reqwest::Error>) -> Result<()> {
+    let response = response.map_err(|e| match e.is_builder() {
+        // A bad URL or header can't succeed on retry
+        true => SinkError::Invalid(e.to_string()),
+        false => SinkError::Network(e.to_string()),
+    })?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(SinkError::Status(status.as_u16()))
+    }
+}
+
+/// Generic JSON webhook
+pub struct WebhookSink {
+    url: String,
+    headers: HashMap<String, String>,
+    template: Option<String>,
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    async fn send(&self, client: &reqwest::Client, notification: &Notification) -> Result<()> {
+        let payload = match &self.template {
+            Some(template) => render(template, notification, true),
+            None => serde_json::to_string(notification).map_err(|e| SinkError::Invalid(e.to_string()))?,
+        };
+        let mut request = client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(payload);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        check(request.send().await).await
+    }
+}
+
+/// Slack, Discord and Mattermost incoming webhooks
+pub struct ChatSink {
+    url: String,
+    flavor: ChatFlavor,
+    template: Option<String>,
+}
+
+#[async_trait]
+impl NotificationSink for ChatSink {
+    async fn send(&self, client: &reqwest::Client, notification: &Notification) -> Result<()> {
+        // Slack's mrkdwn bolds with single asterisks, the others use Markdown
+        let bold = match self.flavor {
+            ChatFlavor::Slack => "*",
+            ChatFlavor::Discord | ChatFlavor::Mattermost => "**",
+        };
+        let text = match &self.template {
+            Some(template) => render(template, notification, false),
+            None => match (&notification.markdown, self.flavor) {
+                (Some(markdown), ChatFlavor::Slack) => slack_mrkdwn(markdown),
+                (Some(markdown), _) => markdown.clone(),
+                (None, _) => format!("{bold}{}{bold}\n{}", notification.title, notification.body),
+            },
+        };
+        let payload = match self.flavor {
+            ChatFlavor::Slack | ChatFlavor::Mattermost => serde_json::json!({ "text": text }),
+            ChatFlavor::Discord => serde_json::json!({ "content": text }),
+        };
+        check(client.post(&self.url).json(&payload).send().await).await
+    }
+}
+
+/// Convert the Markdown we generate to Slack mrkdwn: headings become bold
+/// lines and `**bold**` becomes `*bold*`
+fn slack_mrkdwn(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(|line| {
+            let heading = line.trim_start_matches('#');
+            if heading.len() < line.len() && heading.starts_with(' ') {
+                format!("*{}*", heading.trim().replace("**", ""))
+            } else {
+                line.replace("**", "*")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn push_priority(severity: Severity) -> u8 {
+    match severity {
+        Severity::Info => 3,
+        Severity::Warning => 4,
+        Severity::Critical => 5,
+    }
+}
+
+/// ntfy topic
+pub struct NtfySink {
+    url: String,
+    token: Option<String>,
+    template: Option<String>,
+}
+
+#[async_trait]
+impl NotificationSink for NtfySink {
+    async fn send(&self, client: &reqwest::Client, notification: &Notification) -> Result<()> {
+        let body = match &self.template {
+            Some(template) => render(template, notification, false),
+            None => notification.markdown.clone().unwrap_or_else(|| notification.body.clone()),
+        };
+        let mut request = client
+            .post(&self.url)
+            .header("Title", &notification.title)
+            .header("Priority", push_priority(notification.severity).to_string())
+            .header("Tags", &notification.event)
+            .body(body);
+        if self.template.is_none() && notification.markdown.is_some() {
+            request = request.header("Markdown", "yes");
+        }
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        check(request.send().await).await
+    }
+}
+
+/// Gotify application
+pub struct GotifySink {
+    url: String,
+    token: String,
+    template: Option<String>,
+}
+
+#[async_trait]
+impl NotificationSink for GotifySink {
+    async fn send(&self, client: &reqwest::Client, notification: &Notification) -> Result<()> {
+        let (message, content_type) = match (&self.template, &notification.markdown) {
+            (Some(template), _) => (render(template, notification, false), "text/plain"),
+            (None, Some(markdown)) => (markdown.clone(), "text/markdown"),
+            (None, None) => (notification.body.clone(), "text/plain"),
+        };
+        // Gotify priorities run 0-10
+        let payload = serde_json::json!({
+            "title": notification.title,
+            "message": message,
+            "priority": push_priority(notification.severity) * 2,
+            "extras": { "client::display": { "contentType": content_type } },
+        });
+        let url = format!("{}/message", self.url.trim_end_matches('/'));
+        let request = client.post(url).header("X-Gotify-Key", &self.token).json(&payload);
+        check(request.send().await).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::credentials::MemoryStore;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answer one request per status code; returns the raw requests received
+    async fn listen(statuses: Vec<u16>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&raw).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if raw.len() >= end + 4 + length || n == 0 {
+                            requests.push(text);
+                            break;
+                        }
+                    }
+                }
+                let response = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn alert() -> Notification {
+        Notification::new("usage_alert", Severity::Warning, Some("claude"), "claude Usage Warning", "Session \"usage\" at 85%")
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retries_transient_failures() {
+        let (url, server) = listen(vec![503, 200]).await;
+        let config = SinkConfig {
+            id: "hook".into(),
+            enabled: true,
+            min_severity: Severity::Info,
+            target: SinkTarget::Webhook { url, headers: HashMap::from([("X-Team".into(), "infra".into())]) },
+            template: Some(r#"{"msg":"{provider}: {body}"}"#.into()),
+            secret_key: None,
+        };
+        deliver(config.build().as_ref(), &alert()).await.unwrap();
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].to_ascii_lowercase().contains("x-team: infra"));
+        assert!(requests[1].ends_with(r#"{"msg":"claude: Session \"usage\" at 85%"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_chat_and_push_payloads() {
+        let (url, server) = listen(vec![200, 200, 400]).await;
+        let slack = SinkConfig {
+            id: "slack".into(),
+            enabled: true,
+            min_severity: Severity::Warning,
+            target: SinkTarget::Chat { url: url.clone(), flavor: ChatFlavor::Slack },
+            template: None,
+            secret_key: None,
+        };
+        assert!(slack.accepts(Severity::Critical) && !slack.accepts(Severity::Info));
+        deliver(slack.build().as_ref(), &alert()).await.unwrap();
+
+        let ntfy = SinkConfig {
+            target: SinkTarget::Ntfy { url: url.clone(), token: None },
+            ..slack.clone()
+        };
+        deliver(ntfy.build().as_ref(), &alert()).await.unwrap();
+
+        // Client errors are not retried
+        let err = deliver(ntfy.build().as_ref(), &alert()).await.unwrap_err();
+        assert!(matches!(err, SinkError::Status(400)));
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains(r#""text":"*claude Usage Warning*\nSession \"usage\" at 85%""#));
+        assert!(requests[1].contains("title: claude Usage Warning") || requests[1].contains("Title: claude Usage Warning"));
+        assert!(requests[1].ends_with("Session \"usage\" at 85%"));
+    }
+
+    #[test]
+    fn test_secrets_are_kept_in_the_credential_store() {
+        let store = MemoryStore::new();
+        let chat = SinkConfig {
+            id: "team".into(),
+            enabled: true,
+            min_severity: Severity::Warning,
+            target: SinkTarget::Chat { url: "https://hooks.slack.com/services/T0/B0/secret".into(), flavor: ChatFlavor::Slack },
+            template: None,
+            secret_key: None,
+        };
+        let ntfy = SinkConfig {
+            id: "phone".into(),
+            target: SinkTarget::Ntfy { url: "https://ntfy.sh/topic".into(), token: None },
+            ..chat.clone()
+        };
+        let mut sinks = vec![chat.clone(), ntfy.clone()];
+
+        let sealed = seal_secrets(&store, &mut sinks, &[]).unwrap();
+        assert_eq!(sealed, vec!["notification_sink.team".to_string()]);
+        let saved = serde_json::to_string(&sinks).unwrap();
+        assert!(!saved.contains("hooks.slack.com"));
+        assert_eq!(sinks[1], ntfy);
+        assert!(sinks.iter().all(|sink| sink.validate().is_ok()));
+
+        let mut loaded: Vec<SinkConfig> = serde_json::from_str(&saved).unwrap();
+        unseal_secrets(&store, &mut loaded);
+        assert_eq!(loaded, vec![chat, ntfy.clone()]);
+
+        // Removing the sink removes its entry
+        let sealed = seal_secrets(&store, &mut [ntfy], &sealed).unwrap();
+        assert!(sealed.is_empty());
+        assert_eq!(store.keys().unwrap(), vec![keys::NOTIFICATION_SINKS.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_builder_errors_are_invalid() {
+        let sink = WebhookSink {
+            url: "http://127.0.0.1:9".into(),
+            headers: HashMap::from([("bad header".into(), "x".into())]),
+            template: None,
+        };
+        assert!(matches!(deliver(&sink, &alert()).await, Err(SinkError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_slack_mrkdwn() {
+        let markdown = "## Daily usage digest\n\n### claude\n- Session 40%\n- **Error:** expired token";
+        assert_eq!(
+            slack_mrkdwn(markdown),
+            "*Daily usage digest*\n\n*claude*\n- Session 40%\n- *Error:* expired token"
+        );
+    }
+
+    #[test]
+    fn test_render_uses_template_values_once() {
+        let notification = Notification::new("usage_alert", Severity::Warning, Some("claude"), "{body}", "at {percent}")
+            .with_values(HashMap::from([("percent", "85".to_string()), ("resets_in", String::new())]));
+        assert_eq!(render("{title} / {body} / {percent}%[ resets in {resets_in}]", &notification, false), "{body} / at {percent} / 85%");
+        assert_eq!(render(r#"{"p":[{percent}]}"#, &notification, true), r#"{"p":[85]}"#);
+    }
+}
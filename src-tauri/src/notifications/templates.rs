@@ -0,0 +1,443 @@
+//! Notification title and body templates
+//!
+//! Templates use `{placeholder}` fields, e.g. `{provider} at {percent}%`. Text
+//! in square brackets is optional and dropped when any placeholder inside it
+//! has no value, e.g. `[, resets in {resets_in}]`. Users can override the
+//! built-in text per message kind. Sink payload templates use the same engine:
+//! they see these placeholders plus the notification's own fields
+//! ([`NOTIFICATION_FIELDS`]), so desktop and sink text never disagree.
+
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::anomaly::UsageSpike;
+use super::rules::{Alert, AlertWindow, Restored, Severity};
+use super::sinks::Notification;
+
+/// Placeholders every template may use
+pub const PLACEHOLDERS: &[&str] = &[
+    "provider",
+    "window",
+    "model",
+    "percent",
+    "used",
+    "limit",
+    "remaining",
+    "resets_in",
+    "reset_at",
+    "severity",
+    "rate",
+    "baseline",
+    "runs_out_in",
+    "runs_out_at",
+];
+
+/// Notification fields sink templates may use in addition to [`PLACEHOLDERS`]
+pub const NOTIFICATION_FIELDS: &[&str] = &["title", "body", "event", "timestamp"];
+
+fn is_placeholder(name: &str) -> bool {
+    PLACEHOLDERS.contains(&name) || NOTIFICATION_FIELDS.contains(&name)
+}
+
+/// Placeholder-shaped: lowercase letters and underscores, so JSON braces pass through
+fn is_name(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+}
+
+/// Message kinds that can be overridden, as `<event>.<window>` (spikes aren't
+/// per window, but say so when the window is already used up)
+pub const KINDS: &[&str] = &[
+    "alert.session",
+    "alert.weekly",
+    "alert.model",
+    "alert.credits",
+    "restored.session",
+    "restored.weekly",
+    "restored.model",
+    "restored.credits",
+    "spike",
+    "spike.exhausted",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageTemplate {
+    pub title: String,
+    pub body: String,
+}
+
+impl MessageTemplate {
+    fn new(title: &str, body: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            body: body.to_string(),
+        }
+    }
+}
+
+/// How clock times such as `{reset_at}` are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeFormat {
+    /// 12- or 24-hour clock according to the OS locale
+    #[default]
+    Locale,
+    /// `15:30`
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    /// `3:30 PM`
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+/// Locales (language_REGION) that write times on a 12-hour clock
+const TWELVE_HOUR_LOCALES: &[&str] = &[
+    "en_US", "en_CA", "en_AU", "en_NZ", "en_PH", "en_IN", "hi_IN", "ko_KR", "ar_EG", "ar_SA",
+];
+
+impl TimeFormat {
+    fn twelve_hour(self) -> bool {
+        match self {
+            TimeFormat::TwelveHour => true,
+            TimeFormat::TwentyFourHour => false,
+            // Reads the user's regional settings on macOS and Windows, and LC_*/LANG elsewhere
+            TimeFormat::Locale => sys_locale::get_locale().is_some_and(|locale| uses_twelve_hour(&locale)),
+        }
+    }
+
+    /// Clock time, with the date when it isn't within the next day. Dates use
+    /// ISO `YYYY-MM-DD` rather than localized day names, which chrono can't
+    /// produce for arbitrary locales.
+    fn format(self, at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        let local = at.with_timezone(&Local);
+        let time = if self.twelve_hour() {
+            local.format("%-I:%M %p")
+        } else {
+            local.format("%H:%M")
+        };
+        if at - now < Duration::hours(24) {
+            time.to_string()
+        } else {
+            format!("{} {}", local.format("%Y-%m-%d"), time)
+        }
+    }
+}
+
+fn uses_twelve_hour(locale: &str) -> bool {
+    let name = locale.split(['.', '@']).next().unwrap_or_default().replace('-', "_");
+    TWELVE_HOUR_LOCALES.contains(&name.as_str())
+}
+
+/// User overrides and time formatting for notification text
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TemplateSettings {
+    #[serde(default)]
+    pub time_format: TimeFormat,
+    /// Keyed by message kind, see [`KINDS`]
+    #[serde(default)]
+    pub messages: HashMap<String, MessageTemplate>,
+}
+
+/// Built-in template for a message kind
+pub fn default_template(kind: &str) -> Option<MessageTemplate> {
+    let template = match kind {
+        "alert.session" => MessageTemplate::new("{provider} Usage Warning", "Session usage at {percent}% ({used}/{limit})"),
+        "alert.weekly" => MessageTemplate::new("{provider} Weekly Limit", "Weekly usage at {percent}%"),
+        "alert.model" => MessageTemplate::new(
+            "{provider} {model} Quota",
+            "{model} at {percent}% used[, resets in {resets_in}]",
+        ),
+        "alert.credits" => MessageTemplate::new("{provider} Credits Low", "{remaining} credits remaining"),
+        "restored.session" => {
+            MessageTemplate::new("{provider} Quota Restored", "Session quota is available again[ ({percent}% used)]")
+        }
+        "restored.weekly" => {
+            MessageTemplate::new("{provider} Quota Restored", "Weekly quota is available again[ ({percent}% used)]")
+        }
+        "restored.model" => {
+            MessageTemplate::new("{provider} Quota Restored", "{model} is available again[ ({percent}% used)]")
+        }
+        "restored.credits" => {
+            MessageTemplate::new("{provider} Quota Restored", "{remaining} credits available[ ({percent}% used)]")
+        }
+        "spike" => MessageTemplate::new(
+            "{provider} Usage Spike",
+            "{window} usage rising {rate}%/h (usually {baseline}%/h), now at {percent}%\
+             [; runs out in ~{runs_out_in} at this rate]",
+        ),
+        "spike.exhausted" => MessageTemplate::new(
+            "{provider} Usage Spike",
+            "{window} usage rising {rate}%/h (usually {baseline}%/h), already exhausted",
+        ),
+        _ => return None,
+    };
+    Some(template)
+}
+
+/// Fill `{placeholders}` and keep `[optional]` segments only when all of theirs have values
+pub fn render(template: &str, values: &HashMap<&str, String>) -> String {
+    render_with(template, values, true)
+}
+
+/// Like [`render`]; without `optional_segments` square brackets are plain text,
+/// as JSON payload templates need
+pub fn render_with(template: &str, values: &HashMap<&str, String>, optional_segments: bool) -> String {
+    if !optional_segments {
+        return fill(template, values).0;
+    }
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('[') {
+        out.push_str(&fill(&rest[..open], values).0);
+        let Some(len) = rest[open..].find(']') else {
+            rest = &rest[open..];
+            break;
+        };
+        let (segment, complete) = fill(&rest[open + 1..open + len], values);
+        if complete {
+            out.push_str(&segment);
+        }
+        rest = &rest[open + len + 1..];
+    }
+    out.push_str(&fill(rest, values).0);
+    out
+}
+
+/// Substitute placeholders in one pass, so values are never expanded again;
+/// also reports whether every one had a value
+fn fill(text: &str, values: &HashMap<&str, String>) -> (String, bool) {
+    let mut out = String::new();
+    let mut complete = true;
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let name = rest[open + 1..].split_once('}').map(|(name, _)| name).filter(|name| is_name(name));
+        let Some(name) = name else {
+            // Not a placeholder (e.g. a JSON object); keep the brace
+            out.push('{');
+            rest = &rest[open + 1..];
+            continue;
+        };
+        match values.get(name) {
+            Some(value) if !value.is_empty() => out.push_str(value),
+            _ if is_placeholder(name) => complete = false,
+            _ => out.push_str(&rest[open..open + name.len() + 2]),
+        }
+        rest = &rest[open + name.len() + 2..];
+    }
+    out.push_str(rest);
+    (out, complete)
+}
+
+/// Unknown placeholder names in a template, for validation; `fields` also allows
+/// the notification's own fields (sink templates)
+pub fn unknown_placeholders(template: &str, fields: bool) -> Vec<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .filter(|name| is_name(name))
+        .filter(|name| !(PLACEHOLDERS.contains(name) || fields && NOTIFICATION_FIELDS.contains(name)))
+        .collect()
+}
+
+/// Short relative time until `at`, e.g. `3h 12m`
+pub fn relative(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let minutes = (at - now).num_minutes().max(0);
+    match (minutes / (24 * 60), minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+fn window_label(window: AlertWindow, model: Option<&str>) -> String {
+    match (window, model) {
+        (AlertWindow::Model, Some(model)) => model.to_string(),
+        (AlertWindow::Session, _) => "Session".to_string(),
+        (AlertWindow::Weekly, _) => "Weekly".to_string(),
+        (AlertWindow::Model, None) => "Model".to_string(),
+        (AlertWindow::Credits, _) => "Credits".to_string(),
+    }
+}
+
+fn number(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.0}", v)).unwrap_or_default()
+}
+
+impl TemplateSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (kind, template) in &self.messages {
+            if !KINDS.contains(&kind.as_str()) {
+                return Err(format!("unknown notification template '{}'", kind));
+            }
+            let unknown: Vec<&str> = unknown_placeholders(&template.title, false)
+                .into_iter()
+                .chain(unknown_placeholders(&template.body, false))
+                .collect();
+            if !unknown.is_empty() {
+                return Err(format!("template '{}': unknown placeholder {{{}}}", kind, unknown.join("}, {")));
+            }
+        }
+        Ok(())
+    }
+
+    fn template(&self, kind: &str) -> MessageTemplate {
+        self.messages
+            .get(kind)
+            .cloned()
+            .or_else(|| default_template(kind))
+            .unwrap_or_else(|| MessageTemplate::new("{provider}", ""))
+    }
+
+    /// Render `kind` into a notification carrying its values for sink templates
+    fn message(&self, kind: &str, event: &str, severity: Severity, values: HashMap<&str, String>) -> Notification {
+        let template = self.template(kind);
+        let (title, body) = (render(&template.title, &values), render(&template.body, &values));
+        let provider = values.get("provider").map(String::as_str);
+        Notification::new(event, severity, provider, &title, &body).with_values(values)
+    }
+
+    fn reset_values(&self, values: &mut HashMap<&str, String>, reset_at: Option<DateTime<Utc>>, now: DateTime<Utc>) {
+        if let Some(reset_at) = reset_at {
+            values.insert("resets_in", relative(reset_at, now));
+            values.insert("reset_at", self.time_format.format(reset_at, now));
+        }
+    }
+
+    pub fn alert(&self, alert: &Alert, now: DateTime<Utc>) -> Notification {
+        let mut values = HashMap::from([
+            ("provider", alert.provider.clone()),
+            ("window", window_label(alert.window, alert.model.as_deref())),
+            ("model", alert.model.clone().unwrap_or_default()),
+            ("percent", number(alert.percent)),
+            ("used", alert.used.map(|v| v.to_string()).unwrap_or_default()),
+            ("limit", alert.limit.map(|v| v.to_string()).unwrap_or_default()),
+            ("remaining", number(alert.remaining)),
+            ("severity", alert.severity.as_str().to_string()),
+        ]);
+        self.reset_values(&mut values, alert.reset_at, now);
+        self.message(&format!("alert.{}", alert.window.as_str()), "usage_alert", alert.severity, values)
+    }
+
+    pub fn restored(&self, restored: &Restored) -> Notification {
+        let values = HashMap::from([
+            ("provider", restored.provider.clone()),
+            ("window", window_label(restored.window, restored.model.as_deref())),
+            ("model", restored.model.clone().unwrap_or_default()),
+            ("percent", number(restored.percent)),
+            ("remaining", number(restored.remaining)),
+        ]);
+        let kind = format!("restored.{}", restored.window.as_str());
+        self.message(&kind, "quota_restored", Severity::Info, values)
+    }
+
+    pub fn spike(&self, spike: &UsageSpike, now: DateTime<Utc>) -> Notification {
+        let mut values = HashMap::from([
+            ("provider", spike.provider.clone()),
+            ("window", window_label(spike.window, spike.model.as_deref())),
+            ("model", spike.model.clone().unwrap_or_default()),
+            ("percent", number(Some(spike.percent))),
+            ("rate", number(Some(spike.rate_per_hour))),
+            ("baseline", number(Some(spike.baseline_per_hour))),
+        ]);
+        if let Some(at) = spike.exhausts_at {
+            values.insert("runs_out_in", relative(at, now));
+            values.insert("runs_out_at", self.time_format.format(at, now));
+        }
+        let kind = if spike.percent >= 100.0 { "spike.exhausted" } else { "spike" };
+        self.message(kind, "usage_spike", Severity::Warning, values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(notification: Notification) -> (String, String) {
+        (notification.title, notification.body)
+    }
+
+    fn model_alert(reset_at: Option<DateTime<Utc>>) -> Alert {
+        Alert {
+            rule_id: "pro-model-warning".into(),
+            provider: "gemini".into(),
+            window: AlertWindow::Model,
+            model: Some("gemini-2.5-pro".into()),
+            severity: Severity::Warning,
+            percent: Some(91.4),
+            used: None,
+            limit: None,
+            remaining: None,
+            reset_at,
+        }
+    }
+
+    #[test]
+    fn test_defaults_and_optional_segments() {
+        let now = Utc::now();
+        let settings = TemplateSettings::default();
+
+        let alert = model_alert(Some(now + Duration::minutes(200)));
+        let (title, body) = text(settings.alert(&alert, now));
+        assert_eq!(title, "gemini gemini-2.5-pro Quota");
+        assert_eq!(body, "gemini-2.5-pro at 91% used, resets in 3h 20m");
+
+        // No reset time: the bracketed segment is dropped
+        let (_, body) = text(settings.alert(&model_alert(None), now));
+        assert_eq!(body, "gemini-2.5-pro at 91% used");
+
+        let mut spike = UsageSpike {
+            provider: "claude".into(),
+            window: AlertWindow::Session,
+            model: None,
+            rate_per_hour: 30.0,
+            baseline_per_hour: 5.0,
+            units_per_hour: None,
+            percent: 70.0,
+            exhausts_at: Some(now + Duration::hours(1)),
+        };
+        let (_, body) = text(settings.spike(&spike, now));
+        assert_eq!(body, "Session usage rising 30%/h (usually 5%/h), now at 70%; runs out in ~1h 0m at this rate");
+        (spike.percent, spike.exhausts_at) = (100.0, None);
+        let (_, body) = text(settings.spike(&spike, now));
+        assert_eq!(body, "Session usage rising 30%/h (usually 5%/h), already exhausted");
+    }
+
+    #[test]
+    fn test_overrides_and_time_format() {
+        let now = DateTime::parse_from_rfc3339("2026-10-18T08:00:00Z").unwrap().with_timezone(&Utc);
+        let reset_at = now + Duration::hours(2);
+        let mut settings = TemplateSettings {
+            time_format: TimeFormat::TwelveHour,
+            messages: HashMap::from([(
+                "alert.model".to_string(),
+                MessageTemplate::new("{window}: {percent}%", "Back at {reset_at}"),
+            )]),
+        };
+        assert!(settings.validate().is_ok());
+
+        let (title, body) = text(settings.alert(&model_alert(Some(reset_at)), now));
+        assert_eq!(title, "gemini-2.5-pro: 91%");
+        let local = reset_at.with_timezone(&Local);
+        assert_eq!(body, format!("Back at {}", local.format("%-I:%M %p")));
+
+        settings.time_format = TimeFormat::TwentyFourHour;
+        let (_, body) = text(settings.alert(&model_alert(Some(reset_at)), now));
+        assert_eq!(body, format!("Back at {}", local.format("%H:%M")));
+
+        assert!(uses_twelve_hour("en_US.UTF-8"));
+        assert!(uses_twelve_hour("en-US"));
+        assert!(!uses_twelve_hour("de_DE.UTF-8"));
+
+        let (_, body) = text(settings.alert(&model_alert(Some(now + Duration::days(2))), now));
+        let later = (now + Duration::days(2)).with_timezone(&Local);
+        assert_eq!(body, format!("Back at {}", later.format("%Y-%m-%d %H:%M")));
+
+        settings.messages.insert("spike".into(), MessageTemplate::new("{provider}", "{rate_per_hour}"));
+        assert!(settings.validate().is_err());
+        settings.messages.insert("spike".into(), MessageTemplate::new("", ""));
+        settings.messages.insert("alert.hourly".into(), MessageTemplate::new("", ""));
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Persisted record of sent alerts, so each one fires once per reset period
+//!
+//! Keys identify provider, window, rule and reset period. Entries expire when the
+//! window's reset time passes; windows without a known reset time are cleared
+//! once they stop alerting. The record is kept in `notification_state.json`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::storage::atomic;
+
+const TRACKER_FILE_NAME: &str = "notification_state.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SentEntry {
+    /// `provider:window[:model]`, for clearing a window that stopped alerting
+    scope: String,
+    sent_at: DateTime<Utc>,
+    /// Reset time of the window the alert was about
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Track sent notifications to avoid spam
+#[derive(Default)]
+pub struct NotificationTracker {
+    /// Persistence target; `None` keeps the record in memory only
+    path: Option<PathBuf>,
+    sent: HashMap<String, SentEntry>,
+}
+
+impl NotificationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the record from the app data dir, starting empty if it is missing or
+    /// corrupt (corrupt files are quarantined). A file that can't be read is left
+    /// in place and the record is kept in memory only.
+    pub fn load(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join(TRACKER_FILE_NAME);
+        let (sent, path) = match atomic::load_json(&path, "notification state") {
+            atomic::Loaded::Found(sent) => (sent, Some(path)),
+            atomic::Loaded::Missing | atomic::Loaded::Quarantined => (HashMap::new(), Some(path)),
+            atomic::Loaded::Kept => (HashMap::new(), None),
+        };
+        let mut tracker = Self { path, sent };
+        tracker.prune(Utc::now());
+        tracker
+    }
+
+    /// Check if a notification was already sent in its current reset period
+    pub fn was_sent(&self, key: &str, now: DateTime<Utc>) -> bool {
+        self.sent
+            .get(key)
+            .is_some_and(|entry| entry.expires_at.is_none_or(|at| at > now))
+    }
+
+    /// Mark a notification as sent until `expires_at` (or until its scope is cleared)
+    pub fn mark_sent(&mut self, key: &str, scope: &str, expires_at: Option<DateTime<Utc>>) {
+        self.sent.insert(
+            key.to_string(),
+            SentEntry {
+                scope: scope.to_string(),
+                sent_at: Utc::now(),
+                expires_at,
+            },
+        );
+    }
+
+    /// Drop entries whose reset time has passed; returns whether anything changed
+    pub fn prune(&mut self, now: DateTime<Utc>) -> bool {
+        let before = self.sent.len();
+        self.sent.retain(|_, entry| entry.expires_at.is_none_or(|at| at > now));
+        self.sent.len() != before
+    }
+
+    /// Forget entries without a reset time for a provider's windows that are no
+    /// longer alerting, so crossing the threshold again notifies again
+    pub fn clear_inactive(&mut self, provider: &str, active_scopes: &[String]) -> bool {
+        let prefix = format!("{}:", provider);
+        let before = self.sent.len();
+        self.sent.retain(|_, entry| {
+            entry.expires_at.is_some() || !entry.scope.starts_with(&prefix) || active_scopes.contains(&entry.scope)
+        });
+        self.sent.len() != before
+    }
+
+    /// Forget every entry of one scope; returns whether anything was removed
+    pub fn clear_scope(&mut self, scope: &str) -> bool {
+        let before = self.sent.len();
+        self.sent.retain(|_, entry| entry.scope != scope);
+        self.sent.len() != before
+    }
+
+    /// Reset tracker (call on manual reset)
+    pub fn reset(&mut self) {
+        self.sent.clear();
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else { return };
+        let result = serde_json::to_string_pretty(&self.sent)
+            .map_err(std::io::Error::from)
+            .and_then(|json| atomic::write_atomic(path, json));
+        if let Err(e) = result {
+            log::warn!("Failed to save notification state: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use chrono::Duration;
+
+    #[test]
+    fn test_dedup_expires_at_reset_and_survives_reload() {
+        let dir = std::env::temp_dir().join(format!("limitswatcher-tracker-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let now = Utc::now();
+
+        let mut tracker = NotificationTracker::load(&dir);
+        tracker.mark_sent("claude:session:s80:1", "claude:session", Some(now + Duration::hours(1)));
+        tracker.mark_sent("claude:credits:low:-", "claude:credits", None);
+        tracker.save();
+
+        let mut tracker = NotificationTracker::load(&dir);
+        assert!(tracker.was_sent("claude:session:s80:1", now));
+        assert!(!tracker.was_sent("claude:session:s80:1", now + Duration::hours(2)));
+        assert!(tracker.was_sent("claude:credits:low:-", now));
+
+        // Credits stopped alerting: cleared; the session entry waits for its reset
+        assert!(tracker.clear_inactive("claude", &[]));
+        assert!(!tracker.was_sent("claude:credits:low:-", now));
+        assert!(tracker.was_sent("claude:session:s80:1", now));
+
+        assert!(tracker.prune(now + Duration::hours(2)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_state_is_quarantined() {
+        let dir = std::env::temp_dir().join(format!("limitswatcher-tracker-corrupt-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(TRACKER_FILE_NAME);
+        fs::write(&path, "{not json").unwrap();
+
+        let mut tracker = NotificationTracker::load(&dir);
+        assert!(!tracker.was_sent("claude:credits:low:-", Utc::now()));
+        // Moved aside rather than deleted
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        tracker.mark_sent("claude:credits:low:-", "claude:credits", None);
+        tracker.save();
+        assert!(NotificationTracker::load(&dir).was_sent("claude:credits:low:-", Utc::now()));
+
+        // A file that can't be read is never overwritten
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        let tracker = NotificationTracker::load(&dir);
+        tracker.save();
+        assert!(path.is_dir());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -11,8 +11,9 @@
 use async_trait::async_trait;
 use serde::Deserialize;
 use chrono::{DateTime, Utc};
+use std::time::Duration;
 
-use crate::providers::traits::*;
+use crate::providers::{retry, traits::*};
 use crate::storage::{UsageData, keyring};
 
 const GITHUB_DEVICE_CODE_URL: &str = "https://github.com/login/device/code";
@@ -125,7 +126,7 @@ impl Provider for CopilotProvider {
                     .headers()
                     .get("retry-after")
                     .and_then(|v| v.to_str().ok())
-                    .and_then(|s| s.parse().ok())
+                    .and_then(|s| retry::parse_retry_after(s, Utc::now()))
                     .unwrap_or(60);
                 return Err(ProviderError::RateLimited(retry));
             }
@@ -262,6 +263,16 @@ impl Provider for CopilotProvider {
         Ok(())
     }
     
+    async fn reload_credentials(&mut self) {
+        // A locked vault yields an error here, which drops the in-memory token
+        self.token = keyring::get_credential(keyring::keys::COPILOT_TOKEN).ok().flatten();
+    }
+    
+    fn min_refresh_interval(&self) -> Duration {
+        // copilot_internal is undocumented; keep polling polite
+        Duration::from_secs(120)
+    }
+    
     fn auth_status(&self) -> AuthStatus {
         if self.pending_device_code.is_some() {
             AuthStatus::Authenticating {
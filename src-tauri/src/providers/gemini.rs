@@ -15,10 +15,12 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
 
-use crate::providers::traits::*;
+use crate::providers::token::{self, ExpiringToken, TokenManager, TokenRefresher, DEFAULT_REFRESH_SKEW};
+use crate::providers::{retry, traits::*};
 use crate::storage::{UsageData, ModelQuota};
 
 // Cloud Code Private API endpoints
@@ -27,7 +29,7 @@ const CLOUD_CODE_ASSIST_URL: &str = "https://cloudcode-pa.googleapis.com/v1inter
 const GCP_PROJECTS_URL: &str = "https://cloudresourcemanager.googleapis.com/v1/projects";
 
 pub struct GeminiProvider {
-    credentials: Arc<RwLock<Option<GeminiCredentials>>>,
+    credentials: TokenManager<GeminiCredentials>,
     client: reqwest::Client,
     project_id: Arc<RwLock<Option<String>>>,
     tier: Arc<RwLock<Option<GeminiUserTier>>>,
@@ -54,6 +56,16 @@ fn default_token_uri() -> String {
     "https://oauth2.googleapis.com/token".to_string()
 }
 
+impl ExpiringToken for GeminiCredentials {
+    fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expiry_date.and_then(DateTime::from_timestamp_millis)
+    }
+}
+
 #[derive(Debug, Clone)]
 enum GeminiUserTier {
     Free,
@@ -121,7 +133,7 @@ struct JwtClaims {
 impl GeminiProvider {
     pub fn new() -> Self {
         let provider = Self {
-            credentials: Arc::new(RwLock::new(None)),
+            credentials: TokenManager::new(DEFAULT_REFRESH_SKEW),
             client: reqwest::Client::new(),
             project_id: Arc::new(RwLock::new(None)),
             tier: Arc::new(RwLock::new(None)),
@@ -140,7 +152,7 @@ impl GeminiProvider {
                     if let Some(info) = account_info {
                         *provider.account_info.blocking_write() = Some(info);
                     }
-                    *provider.credentials.blocking_write() = Some(creds);
+                    provider.credentials.set(Some(creds));
                 }
             }
         }
@@ -164,7 +176,7 @@ impl GeminiProvider {
                         }
                     }
 
-                    *self.credentials.write().await = Some(creds);
+                    self.credentials.set(Some(creds));
                 }
             }
         }
@@ -200,98 +212,11 @@ impl GeminiProvider {
     }
 
     async fn ensure_valid_token(&self) -> ProviderResult<String> {
-        let creds = {
-            let creds_guard = self.credentials.read().await;
-            creds_guard.clone().ok_or_else(|| {
-                ProviderError::AuthRequired
-            })?
-        };
-
-        // Check if token is expired (expiry_date is in milliseconds)
-        let needs_refresh = if let Some(expiry_ms) = creds.expiry_date {
-            let now_ms = Utc::now().timestamp_millis();
-            expiry_ms < now_ms
-        } else {
-            false
-        };
-
-        if needs_refresh {
-            self.refresh_token().await?;
-            let creds_guard = self.credentials.read().await;
-            Ok(creds_guard.as_ref().unwrap().access_token.clone())
-        } else {
-            Ok(creds.access_token.clone())
-        }
+        self.credentials.access_token(self).await
     }
 
-    async fn refresh_token(&self) -> ProviderResult<()> {
-        let creds = {
-            let creds_guard = self.credentials.read().await;
-            creds_guard.clone().ok_or(ProviderError::AuthRequired)?
-        };
-
-        // Extract OAuth credentials from Gemini CLI installation
-        let (client_id, client_secret) = if let (Some(id), Some(secret)) = (&creds.client_id, &creds.client_secret) {
-            (id.clone(), secret.clone())
-        } else {
-            Self::extract_oauth_credentials().await?
-        };
-
-        #[derive(Deserialize)]
-        struct TokenResponse {
-            access_token: String,
-            #[serde(default)]
-            expires_in: Option<i64>,
-            #[serde(default)]
-            id_token: Option<String>,
-        }
-
-        let response = self.client
-            .post(&creds.token_uri)
-            .form(&[
-                ("grant_type", "refresh_token"),
-                ("refresh_token", &creds.refresh_token),
-                ("client_id", &client_id),
-                ("client_secret", &client_secret),
-            ])
-            .send()
-            .await
-            .map_err(|e| ProviderError::Network(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(ProviderError::AuthFailed(
-                "Token refresh failed. Run 'gemini auth' to re-authenticate.".into()
-            ));
-        }
-
-        let token: TokenResponse = response
-            .json()
-            .await
-            .map_err(|e| ProviderError::Parse(e.to_string()))?;
-
-        // Calculate new expiry time
-        let expiry_date = if let Some(expires_in) = token.expires_in {
-            Some(Utc::now().timestamp_millis() + (expires_in * 1000))
-        } else {
-            None
-        };
-
-        // Update credentials in memory
-        let mut updated_creds = creds.clone();
-        updated_creds.access_token = token.access_token.clone();
-        updated_creds.expiry_date = expiry_date;
-        if let Some(new_id_token) = token.id_token {
-            updated_creds.id_token = Some(new_id_token.clone());
-
-            // Update account info
-            if let Ok(account_info) = Self::extract_jwt_claims(&new_id_token) {
-                *self.account_info.write().await = Some(account_info);
-            }
-        }
-
-        *self.credentials.write().await = Some(updated_creds.clone());
-
-        // Persist to disk
+    /// Persist refreshed credentials back to the Gemini CLI file
+    async fn save_credentials(&self, updated_creds: &GeminiCredentials) -> ProviderResult<()> {
         if let Some(path) = Self::get_credentials_path() {
             let json = serde_json::to_string_pretty(&updated_creds)
                 .map_err(|e| ProviderError::Provider(format!("Serialize error: {}", e)))?;
@@ -453,7 +378,7 @@ impl GeminiProvider {
                 let retry_after = response.headers()
                     .get("retry-after")
                     .and_then(|v| v.to_str().ok())
-                    .and_then(|s| s.parse::<u64>().ok())
+                    .and_then(|s| retry::parse_retry_after(s, Utc::now()))
                     .unwrap_or(60);
                 return Err(ProviderError::RateLimited(retry_after));
             }
@@ -526,6 +451,47 @@ impl GeminiProvider {
     }
 }
 
+#[async_trait]
+impl TokenRefresher<GeminiCredentials> for GeminiProvider {
+    async fn refresh(&self, creds: &GeminiCredentials) -> ProviderResult<GeminiCredentials> {
+        // Extract OAuth credentials from Gemini CLI installation
+        let (client_id, client_secret) = if let (Some(id), Some(secret)) = (&creds.client_id, &creds.client_secret) {
+            (id.clone(), secret.clone())
+        } else {
+            Self::extract_oauth_credentials().await?
+        };
+
+        let token = token::refresh_grant(
+            &self.client,
+            &creds.token_uri,
+            &creds.refresh_token,
+            &client_id,
+            Some(&client_secret),
+        )
+        .await?;
+
+        let mut updated_creds = creds.clone();
+        updated_creds.expiry_date = token.expires_at().map(|t| t.timestamp_millis());
+        updated_creds.access_token = token.access_token;
+        if let Some(refresh_token) = token.refresh_token {
+            updated_creds.refresh_token = refresh_token;
+        }
+        if let Some(new_id_token) = token.id_token {
+            // Update account info
+            if let Ok(account_info) = Self::extract_jwt_claims(&new_id_token) {
+                *self.account_info.write().await = Some(account_info);
+            }
+            updated_creds.id_token = Some(new_id_token);
+        }
+
+        // The new token works either way; a failed write only costs a refresh next launch
+        if let Err(e) = self.save_credentials(&updated_creds).await {
+            log::warn!("Failed to persist refreshed Gemini credentials: {}", e);
+        }
+        Ok(updated_creds)
+    }
+}
+
 #[async_trait]
 impl Provider for GeminiProvider {
     fn info(&self) -> ProviderInfo {
@@ -542,7 +508,7 @@ impl Provider for GeminiProvider {
     }
 
     async fn is_authenticated(&self) -> bool {
-        self.credentials.read().await.is_some()
+        self.credentials.is_present()
     }
 
     async fn fetch_usage(&self) -> ProviderResult<UsageData> {
@@ -604,7 +570,7 @@ impl Provider for GeminiProvider {
     async fn complete_auth(&mut self, _response: AuthResponse) -> ProviderResult<()> {
         self.load_credentials().await;
 
-        let is_authenticated = self.credentials.read().await.is_some();
+        let is_authenticated = self.credentials.is_present();
         if is_authenticated {
             Ok(())
         } else {
@@ -615,41 +581,59 @@ impl Provider for GeminiProvider {
     }
 
     async fn logout(&mut self) -> ProviderResult<()> {
-        *self.credentials.write().await = None;
+        self.credentials.set(None);
         *self.project_id.write().await = None;
         *self.tier.write().await = None;
         *self.account_info.write().await = None;
         Ok(())
     }
 
+    fn min_refresh_interval(&self) -> Duration {
+        // Private Cloud Code API; don't poll it harder than the CLI would
+        Duration::from_secs(120)
+    }
+
+    fn credentials_expire_at(&self) -> Option<DateTime<Utc>> {
+        // The access token only matters when it can't be refreshed
+        let creds = self.credentials.get()?;
+        let refreshable = !creds.refresh_token.is_empty() && self.credentials.refresh_failure().is_none();
+        if refreshable {
+            None
+        } else {
+            creds.expires_at()
+        }
+    }
+
     fn auth_status(&self) -> AuthStatus {
+        if let Some(reason) = self.credentials.refresh_failure() {
+            return AuthStatus::RefreshFailed {
+                message: format!("{}. Run 'gemini auth' to re-authenticate.", reason),
+            };
+        }
+
         // We need to access RwLock in a sync context - use try_read instead
-        let creds = self.credentials.try_read().ok();
         let account_info = self.account_info.try_read().ok().and_then(|g| g.clone());
         let tier = self.tier.try_read().ok().and_then(|g| g.clone());
 
-        if let Some(creds_guard) = creds {
-            if creds_guard.is_some() {
-                let user_display = if let Some(info) = account_info {
-                    let plan = self.get_plan_display(&tier, &Some(info.clone()));
-                    format!("{} ({})", info.email, plan)
-                } else {
-                    "via Gemini CLI".to_string()
-                };
-
-                let expires = creds_guard.as_ref()
-                    .and_then(|c| c.expiry_date)
-                    .map(|ms| {
-                        DateTime::from_timestamp_millis(ms)
-                            .map(|dt| dt.to_rfc3339())
-                            .unwrap_or_default()
-                    });
-
-                return AuthStatus::Authenticated {
-                    user: Some(user_display),
-                    expires,
-                };
-            }
+        if let Some(creds) = self.credentials.get() {
+            let user_display = if let Some(info) = account_info {
+                let plan = self.get_plan_display(&tier, &Some(info.clone()));
+                format!("{} ({})", info.email, plan)
+            } else {
+                "via Gemini CLI".to_string()
+            };
+
+            let expires = creds.expiry_date
+                .map(|ms| {
+                    DateTime::from_timestamp_millis(ms)
+                        .map(|dt| dt.to_rfc3339())
+                        .unwrap_or_default()
+                });
+
+            return AuthStatus::Authenticated {
+                user: Some(user_display),
+                expires,
+            };
         }
 
         AuthStatus::NotAuthenticated
@@ -0,0 +1,131 @@
+//! Per-provider health metrics for diagnostics
+//!
+//! Tracks outcomes and latency of usage fetches so the UI can tell a provider
+//! that has been failing for hours from one that failed once.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::traits::ProviderError;
+
+/// Errors kept per provider
+pub const ERROR_HISTORY_LEN: usize = 20;
+
+/// Requests the error rate and latency averages are computed over
+const SAMPLE_WINDOW: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorRecord {
+    pub at: DateTime<Utc>,
+    /// `ProviderError` variant, e.g. `network` or `rate_limited`
+    pub kind: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProviderHealth {
+    last_success: Option<DateTime<Utc>>,
+    last_failure: Option<DateTime<Utc>>,
+    consecutive_failures: u32,
+    total_requests: u64,
+    total_failures: u64,
+    /// `(succeeded, latency)` of the most recent requests
+    samples: VecDeque<(bool, Duration)>,
+    errors: VecDeque<ErrorRecord>,
+}
+
+/// Health metrics as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSnapshot {
+    pub last_success: Option<String>,
+    pub last_failure: Option<String>,
+    pub consecutive_failures: u32,
+    pub total_requests: u64,
+    pub total_failures: u64,
+    /// Failure fraction over the last requests (0.0 - 1.0)
+    pub error_rate: f64,
+    pub last_latency_ms: Option<u64>,
+    pub avg_latency_ms: Option<u64>,
+    /// Newest first
+    pub recent_errors: Vec<ErrorRecord>,
+}
+
+impl ProviderHealth {
+    pub fn record_success(&mut self, latency: Duration) {
+        self.last_success = Some(Utc::now());
+        self.consecutive_failures = 0;
+        self.push_sample(true, latency);
+    }
+
+    pub fn record_failure(&mut self, error: &ProviderError, latency: Duration) {
+        let now = Utc::now();
+        self.last_failure = Some(now);
+        self.consecutive_failures += 1;
+        self.total_failures += 1;
+        self.push_sample(false, latency);
+
+        if self.errors.len() == ERROR_HISTORY_LEN {
+            self.errors.pop_back();
+        }
+        self.errors.push_front(ErrorRecord {
+            at: now,
+            kind: error.kind(),
+            message: error.to_string(),
+        });
+    }
+
+    fn push_sample(&mut self, succeeded: bool, latency: Duration) {
+        self.total_requests += 1;
+        if self.samples.len() == SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((succeeded, latency));
+    }
+
+    pub fn snapshot(&self) -> HealthSnapshot {
+        let sampled = self.samples.len();
+        let failures = self.samples.iter().filter(|(ok, _)| !ok).count();
+        let total_latency: Duration = self.samples.iter().map(|(_, latency)| *latency).sum();
+
+        HealthSnapshot {
+            last_success: self.last_success.map(|t| t.to_rfc3339()),
+            last_failure: self.last_failure.map(|t| t.to_rfc3339()),
+            consecutive_failures: self.consecutive_failures,
+            total_requests: self.total_requests,
+            total_failures: self.total_failures,
+            error_rate: if sampled == 0 { 0.0 } else { failures as f64 / sampled as f64 },
+            last_latency_ms: self.samples.back().map(|(_, latency)| latency.as_millis() as u64),
+            avg_latency_ms: (sampled > 0).then(|| (total_latency / sampled as u32).as_millis() as u64),
+            recent_errors: self.errors.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_tracks_rates_latency_and_errors() {
+        let mut health = ProviderHealth::default();
+        health.record_success(Duration::from_millis(100));
+        health.record_failure(&ProviderError::Network("reset".into()), Duration::from_millis(300));
+        health.record_failure(&ProviderError::RateLimited(30), Duration::from_millis(200));
+
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.consecutive_failures, 2);
+        assert!((snapshot.error_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(snapshot.avg_latency_ms, Some(200));
+        assert_eq!(snapshot.last_latency_ms, Some(200));
+        assert_eq!(snapshot.recent_errors[0].kind, "rate_limited");
+        assert_eq!(snapshot.recent_errors[1].kind, "network");
+
+        for _ in 0..ERROR_HISTORY_LEN {
+            health.record_failure(&ProviderError::TokenExpired, Duration::ZERO);
+        }
+        assert_eq!(health.snapshot().recent_errors.len(), ERROR_HISTORY_LEN);
+        assert_eq!(health.snapshot().total_failures, 22);
+    }
+}
@@ -3,6 +3,9 @@ pub mod copilot;
 pub mod claude;
 pub mod gemini;
 pub mod antigravity;
+pub mod health;
+pub mod retry;
+pub mod token;
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -14,6 +17,8 @@ pub use traits::*;
 pub struct ProviderRegistry {
     providers: HashMap<String, Arc<RwLock<dyn Provider>>>,
     enabled: HashMap<String, bool>,
+    /// Display order (registration order until configured)
+    order: Vec<String>,
 }
 
 impl ProviderRegistry {
@@ -21,6 +26,7 @@ impl ProviderRegistry {
         let mut registry = Self {
             providers: HashMap::new(),
             enabled: HashMap::new(),
+            order: Vec::new(),
         };
         
         // Register all providers
@@ -36,7 +42,8 @@ impl ProviderRegistry {
         let info = provider.info();
         let id = info.id.clone();
         self.providers.insert(id.clone(), Arc::new(RwLock::new(provider)));
-        self.enabled.insert(id, false); // Disabled by default
+        self.enabled.insert(id.clone(), false); // Disabled by default
+        self.order.push(id);
     }
     
     pub fn get_provider(&self, id: &str) -> Option<Arc<RwLock<dyn Provider>>> {
@@ -53,15 +60,30 @@ impl ProviderRegistry {
         }
     }
     
+    /// Reorder providers; unknown ids are ignored and unlisted ones keep their relative order at the end
+    pub fn set_order(&mut self, order: Vec<String>) {
+        let mut next: Vec<String> = order
+            .into_iter()
+            .filter(|id| self.providers.contains_key(id))
+            .collect();
+        for id in &self.order {
+            if !next.contains(id) {
+                next.push(id.clone());
+            }
+        }
+        self.order = next;
+    }
+    
+    /// Provider ids in display order
     pub fn all_provider_names(&self) -> Vec<String> {
-        self.providers.keys().cloned().collect()
+        self.order.clone()
     }
     
     pub fn enabled_providers(&self) -> Vec<(String, Arc<RwLock<dyn Provider>>)> {
-        self.providers
+        self.order
             .iter()
-            .filter(|(id, _)| self.is_enabled(id))
-            .map(|(id, p)| (id.clone(), p.clone()))
+            .filter(|id| self.is_enabled(id))
+            .filter_map(|id| self.providers.get(id).map(|p| (id.clone(), p.clone())))
             .collect()
     }
     
@@ -0,0 +1,182 @@
+//! Retry policy for scheduled refreshes, driven by `ProviderError`
+//!
+//! - `RateLimited` waits for the server's Retry-After
+//! - `Network`/`Parse`/`Provider` back off exponentially with jitter
+//! - auth errors stop polling until the user re-authenticates
+//! - repeated failures open a circuit; after it cools down one probe is let through
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use super::traits::ProviderError;
+
+/// First retry delay after a transient failure
+const BACKOFF_BASE: Duration = Duration::seconds(30);
+
+/// Longest transient backoff
+const BACKOFF_MAX: Duration = Duration::minutes(30);
+
+/// Consecutive transient failures that open the circuit
+const CIRCUIT_THRESHOLD: u32 = 5;
+
+/// How long an open circuit blocks polling before a probe
+const CIRCUIT_OPEN_DURATION: Duration = Duration::minutes(30);
+
+/// Parse a Retry-After header value (delta-seconds or HTTP-date) into seconds
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((at - now).num_seconds().max(0) as u64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryState {
+    Ok,
+    RateLimited,
+    BackingOff,
+    CircuitOpen,
+    /// Polling stopped until the provider is re-authenticated
+    AuthRequired,
+}
+
+/// Retry state shown alongside provider status
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryStatus {
+    pub state: RetryState,
+    pub next_attempt: Option<String>,
+    pub consecutive_failures: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryTracker {
+    state: RetryState,
+    next_attempt: Option<DateTime<Utc>>,
+    consecutive_failures: u32,
+}
+
+impl Default for RetryTracker {
+    fn default() -> Self {
+        Self {
+            state: RetryState::Ok,
+            next_attempt: None,
+            consecutive_failures: 0,
+        }
+    }
+}
+
+impl RetryTracker {
+    pub fn state(&self) -> RetryState {
+        self.state
+    }
+
+    /// Earliest time the next scheduled attempt may run, if a failure deferred it
+    pub fn next_attempt(&self) -> Option<DateTime<Utc>> {
+        self.next_attempt
+    }
+
+    /// Whether the scheduler may poll now
+    pub fn can_attempt(&self, now: DateTime<Utc>) -> bool {
+        match self.state {
+            RetryState::AuthRequired => false,
+            _ => self.next_attempt.is_none_or(|at| now >= at),
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn record_failure(&mut self, error: &ProviderError, now: DateTime<Utc>) {
+        self.record_failure_with_jitter(error, now, rand::random::<f64>());
+    }
+
+    /// `jitter` in `[0, 1)` picks a delay between half and all of the backoff
+    fn record_failure_with_jitter(&mut self, error: &ProviderError, now: DateTime<Utc>, jitter: f64) {
+        match error {
+            ProviderError::RateLimited(secs) => {
+                // The server told us when; not a fault on either side
+                self.state = RetryState::RateLimited;
+                self.next_attempt = Some(now + Duration::seconds(*secs as i64));
+            }
+            ProviderError::AuthRequired
+            | ProviderError::AuthFailed(_)
+            | ProviderError::TokenExpired
+            | ProviderError::TokenRefreshFailed(_)
+            | ProviderError::NotConfigured => {
+                self.state = RetryState::AuthRequired;
+                self.next_attempt = None;
+            }
+            ProviderError::Network(_) | ProviderError::Parse(_) | ProviderError::Provider(_) => {
+                self.consecutive_failures += 1;
+                if self.consecutive_failures >= CIRCUIT_THRESHOLD {
+                    self.state = RetryState::CircuitOpen;
+                    self.next_attempt = Some(now + CIRCUIT_OPEN_DURATION);
+                } else {
+                    let exp = BACKOFF_BASE * 2i32.pow(self.consecutive_failures - 1);
+                    let backoff = exp.min(BACKOFF_MAX);
+                    let jittered = backoff.num_milliseconds() as f64 * (0.5 + jitter / 2.0);
+                    self.state = RetryState::BackingOff;
+                    self.next_attempt = Some(now + Duration::milliseconds(jittered as i64));
+                }
+            }
+        }
+    }
+
+    /// Clear an auth stop after the user re-authenticates
+    pub fn clear_auth(&mut self) {
+        if self.state == RetryState::AuthRequired {
+            *self = Self::default();
+        }
+    }
+
+    pub fn status(&self) -> RetryStatus {
+        RetryStatus {
+            state: self.state,
+            next_attempt: self.next_attempt.map(|t| t.to_rfc3339()),
+            consecutive_failures: self.consecutive_failures,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_retry_after("120", now), Some(120));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:30:00 GMT", now), Some(120));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(0));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_backoff_circuit_and_auth() {
+        let now = Utc::now();
+        let network = ProviderError::Network("timeout".into());
+        let mut tracker = RetryTracker::default();
+
+        tracker.record_failure_with_jitter(&network, now, 0.0);
+        assert_eq!(tracker.next_attempt(), Some(now + Duration::seconds(15)));
+        tracker.record_failure_with_jitter(&network, now, 0.999);
+        assert!(tracker.next_attempt().unwrap() <= now + Duration::seconds(60));
+        assert!(!tracker.can_attempt(now));
+
+        for _ in 0..3 {
+            tracker.record_failure_with_jitter(&network, now, 0.5);
+        }
+        assert_eq!(tracker.state(), RetryState::CircuitOpen);
+        assert!(tracker.can_attempt(now + CIRCUIT_OPEN_DURATION));
+
+        tracker.record_success();
+        tracker.record_failure(&ProviderError::TokenExpired, now);
+        assert!(!tracker.can_attempt(now + Duration::days(1)));
+        tracker.clear_auth();
+        assert_eq!(tracker.state(), RetryState::Ok);
+    }
+}
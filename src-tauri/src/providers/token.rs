@@ -0,0 +1,305 @@
+//! Shared OAuth token lifecycle for providers with expiring access tokens
+//!
+//! `TokenManager` hands out access tokens, refreshing them a few minutes before
+//! they expire. Concurrent callers share a single refresh, and a refresh the
+//! provider rejects is remembered so it can be shown as its own auth state.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::sync::Mutex;
+
+use super::retry;
+use super::traits::{ProviderError, ProviderResult};
+
+/// Refresh this long before the access token expires
+pub const DEFAULT_REFRESH_SKEW: Duration = Duration::minutes(5);
+
+/// Credentials holding an access token with an optional expiry
+pub trait ExpiringToken: Clone + Send + Sync {
+    fn access_token(&self) -> &str;
+    fn expires_at(&self) -> Option<DateTime<Utc>>;
+}
+
+/// Provider-specific refresh (token endpoint, client credentials, persistence)
+#[async_trait]
+pub trait TokenRefresher<T>: Send + Sync {
+    async fn refresh(&self, current: &T) -> ProviderResult<T>;
+}
+
+pub struct TokenManager<T> {
+    token: Mutex<Option<T>>,
+    /// Why the last refresh was rejected, until new credentials arrive
+    refresh_failure: Mutex<Option<String>>,
+    /// Held while refreshing so concurrent callers wait for one refresh
+    refreshing: tokio::sync::Mutex<()>,
+    skew: Duration,
+}
+
+impl<T: ExpiringToken> TokenManager<T> {
+    pub fn new(skew: Duration) -> Self {
+        Self {
+            token: Mutex::new(None),
+            refresh_failure: Mutex::new(None),
+            refreshing: tokio::sync::Mutex::new(()),
+            skew,
+        }
+    }
+
+    pub fn get(&self) -> Option<T> {
+        self.token.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.token.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    /// Replace the credentials (new login, reload, logout) and clear any refresh failure
+    pub fn set(&self, token: Option<T>) {
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = token;
+        *self.refresh_failure.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    pub fn refresh_failure(&self) -> Option<String> {
+        self.refresh_failure.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn needs_refresh(&self, token: &T, now: DateTime<Utc>) -> bool {
+        token.expires_at().is_some_and(|at| at - self.skew <= now)
+    }
+
+    /// A usable access token, refreshing first if it is within the skew of expiry
+    pub async fn access_token(&self, refresher: &dyn TokenRefresher<T>) -> ProviderResult<String> {
+        let token = self.get().ok_or(ProviderError::AuthRequired)?;
+        if !self.needs_refresh(&token, Utc::now()) {
+            return Ok(token.access_token().to_string());
+        }
+
+        let _refreshing = self.refreshing.lock().await;
+
+        // Another caller may have refreshed while we waited
+        let token = self.get().ok_or(ProviderError::AuthRequired)?;
+        let now = Utc::now();
+        if !self.needs_refresh(&token, now) {
+            return Ok(token.access_token().to_string());
+        }
+        if let Some(reason) = self.refresh_failure() {
+            // Don't retry a rejected refresh token until the user signs in again
+            return Err(ProviderError::TokenRefreshFailed(reason));
+        }
+
+        match refresher.refresh(&token).await {
+            Ok(refreshed) => {
+                let access_token = refreshed.access_token().to_string();
+                self.set(Some(refreshed));
+                Ok(access_token)
+            }
+            Err(e) => {
+                let rejected = matches!(
+                    e,
+                    ProviderError::TokenRefreshFailed(_) | ProviderError::AuthFailed(_) | ProviderError::AuthRequired
+                );
+                if rejected {
+                    log::warn!("Token refresh rejected: {}", e);
+                    *self.refresh_failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(e.to_string());
+                }
+                // Still inside the skew window: keep using the current token for now
+                let still_valid = token.expires_at().is_some_and(|at| at > now);
+                if still_valid && !rejected {
+                    Ok(token.access_token().to_string())
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+}
+
+/// Wait this long after a 429 that doesn't say when to retry
+const DEFAULT_RETRY_AFTER_SECS: u64 = 60;
+
+/// OAuth2 error codes meaning the refresh token itself was rejected
+const REJECTION_CODES: [&str; 2] = ["invalid_grant", "unauthorized_client"];
+
+/// OAuth2 error body (RFC 6749 section 5.2)
+#[derive(Debug, Default, Deserialize)]
+struct TokenErrorResponse {
+    #[serde(default)]
+    error: String,
+}
+
+/// Response to a standard OAuth2 `refresh_token` grant
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub id_token: Option<String>,
+}
+
+impl TokenResponse {
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_in.map(|secs| Utc::now() + Duration::seconds(secs))
+    }
+}
+
+/// Exchange a refresh token at `token_uri`
+pub async fn refresh_grant(
+    client: &reqwest::Client,
+    token_uri: &str,
+    refresh_token: &str,
+    client_id: &str,
+    client_secret: Option<&str>,
+) -> ProviderResult<TokenResponse> {
+    let mut form = vec![
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token),
+        ("client_id", client_id),
+    ];
+    if let Some(secret) = client_secret {
+        form.push(("client_secret", secret));
+    }
+
+    let response = client
+        .post(token_uri)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| ProviderError::Network(e.to_string()))?;
+
+    let status = response.status();
+    match status.as_u16() {
+        429 => {
+            let retry = response
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| retry::parse_retry_after(s, Utc::now()))
+                .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+            return Err(ProviderError::RateLimited(retry));
+        }
+        400 | 401 => {
+            // Only an explicit rejection of the grant needs a new sign-in
+            let body: TokenErrorResponse = response.json().await.unwrap_or_default();
+            if REJECTION_CODES.contains(&body.error.as_str()) {
+                return Err(ProviderError::TokenRefreshFailed(format!("token endpoint returned {}", body.error)));
+            }
+            return Err(ProviderError::Provider(format!("token endpoint returned {}", status)));
+        }
+        _ if !status.is_success() => {
+            return Err(ProviderError::Provider(format!("token endpoint returned {}", status)));
+        }
+        _ => {}
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| ProviderError::Parse(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone)]
+    struct TestToken(String, DateTime<Utc>);
+
+    impl ExpiringToken for TestToken {
+        fn access_token(&self) -> &str {
+            &self.0
+        }
+        fn expires_at(&self) -> Option<DateTime<Utc>> {
+            Some(self.1)
+        }
+    }
+
+    struct CountingRefresher {
+        calls: AtomicU32,
+        reject: bool,
+    }
+
+    #[async_trait]
+    impl TokenRefresher<TestToken> for CountingRefresher {
+        async fn refresh(&self, _current: &TestToken) -> ProviderResult<TestToken> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            if self.reject {
+                return Err(ProviderError::TokenRefreshFailed("invalid_grant".into()));
+            }
+            Ok(TestToken("fresh".into(), Utc::now() + Duration::hours(1)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_once_before_expiry() {
+        let manager = Arc::new(TokenManager::new(DEFAULT_REFRESH_SKEW));
+        manager.set(Some(TestToken("old".into(), Utc::now() + Duration::minutes(2))));
+        let refresher = Arc::new(CountingRefresher { calls: AtomicU32::new(0), reject: false });
+
+        let mut tasks = Vec::new();
+        for _ in 0..4 {
+            let (manager, refresher) = (manager.clone(), refresher.clone());
+            tasks.push(tokio::spawn(async move { manager.access_token(refresher.as_ref()).await }));
+        }
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), "fresh");
+        }
+        assert_eq!(refresher.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_refresh_is_remembered() {
+        let manager = TokenManager::new(DEFAULT_REFRESH_SKEW);
+        manager.set(Some(TestToken("old".into(), Utc::now() - Duration::minutes(1))));
+        let refresher = CountingRefresher { calls: AtomicU32::new(0), reject: true };
+
+        assert!(matches!(manager.access_token(&refresher).await, Err(ProviderError::TokenRefreshFailed(_))));
+        assert!(manager.refresh_failure().is_some());
+        assert!(manager.access_token(&refresher).await.is_err());
+        assert_eq!(refresher.calls.load(Ordering::SeqCst), 1);
+    }
+
+    /// Answer one request with a canned HTTP response; returns the URL
+    async fn serve_once(response: String) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_refresh_grant_classifies_errors() {
+        let client = reqwest::Client::new();
+
+        let url = serve_once("HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into()).await;
+        let result = refresh_grant(&client, &url, "rt", "client", None).await;
+        assert!(matches!(result, Err(ProviderError::RateLimited(120))));
+        assert!(!result.unwrap_err().is_auth_failure());
+
+        let body = r#"{"error":"invalid_grant"}"#;
+        let url = serve_once(format!(
+            "HTTP/1.1 400 Bad Request\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ))
+        .await;
+        let result = refresh_grant(&client, &url, "rt", "client", None).await;
+        assert!(matches!(result, Err(ProviderError::TokenRefreshFailed(_))));
+
+        let url = serve_once("HTTP/1.1 408 Request Timeout\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into()).await;
+        let result = refresh_grant(&client, &url, "rt", "client", None).await;
+        assert!(matches!(result, Err(ProviderError::Provider(_))));
+    }
+}
@@ -1,9 +1,14 @@
 //! Provider trait definition - implement this for each AI service
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use crate::storage::UsageData;
 
+/// Default floor for automatic polling of a provider
+pub const DEFAULT_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Result type for provider operations
 pub type ProviderResult<T> = Result<T, ProviderError>;
 
@@ -18,6 +23,9 @@ pub enum ProviderError {
     #[error("Token expired")]
     TokenExpired,
     
+    #[error("Token refresh failed: {0}")]
+    TokenRefreshFailed(String),
+    
     #[error("Rate limited, retry after {0} seconds")]
     RateLimited(u64),
     
@@ -34,6 +42,34 @@ pub enum ProviderError {
     NotConfigured,
 }
 
+impl ProviderError {
+    /// Stable variant name for diagnostics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProviderError::AuthRequired => "auth_required",
+            ProviderError::AuthFailed(_) => "auth_failed",
+            ProviderError::TokenExpired => "token_expired",
+            ProviderError::TokenRefreshFailed(_) => "token_refresh_failed",
+            ProviderError::RateLimited(_) => "rate_limited",
+            ProviderError::Network(_) => "network",
+            ProviderError::Parse(_) => "parse",
+            ProviderError::Provider(_) => "provider",
+            ProviderError::NotConfigured => "not_configured",
+        }
+    }
+
+    /// Stored credentials were rejected and the user has to sign in again
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::AuthRequired
+                | ProviderError::AuthFailed(_)
+                | ProviderError::TokenExpired
+                | ProviderError::TokenRefreshFailed(_)
+        )
+    }
+}
+
 /// Authentication method supported by a provider
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AuthMethod {
@@ -95,6 +131,19 @@ pub trait Provider: Send + Sync {
     
     /// Get current auth status for display
     fn auth_status(&self) -> AuthStatus;
+    
+    /// Re-read stored credentials (e.g. after the vault is locked or unlocked)
+    async fn reload_credentials(&mut self) {}
+    
+    /// Shortest interval the scheduler may poll this provider at
+    fn min_refresh_interval(&self) -> Duration {
+        DEFAULT_MIN_REFRESH_INTERVAL
+    }
+    
+    /// When the stored credentials stop working unless the user signs in again, if known
+    fn credentials_expire_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
 }
 
 /// Authentication flow information
@@ -132,6 +181,8 @@ pub enum AuthStatus {
     Authenticating { message: String },
     /// Authenticated successfully
     Authenticated { user: Option<String>, expires: Option<String> },
+    /// Credentials exist but the provider rejected the token refresh
+    RefreshFailed { message: String },
     /// Auth error
     Error { message: String },
 }
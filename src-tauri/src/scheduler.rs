@@ -1,20 +1,69 @@
 //! Background refresh scheduler with configurable intervals
+//!
+//! Each provider is polled on its own interval (the global one unless overridden),
+//! never faster than the provider's declared minimum. Adaptive mode shortens the
+//! interval near limits or resets and backs off while usage stays unchanged.
+//! Failed polls are deferred by the provider's `RetryTracker`.
+//!
+//! Due providers are refreshed concurrently (bounded, with a per-provider timeout)
+//! from a snapshot of the registry, so no registry lock is held during network calls.
+//!
+//! The loop can be paused (scheduled polls stop, explicit refreshes still run) and
+//! woken early by `refresh_now`.
+//!
+//! Known reset times get a one-off poll just after the reset, and a poll that
+//! observes a reset emits `quota-reset`.
 
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, RwLock, Semaphore};
 use tauri::{AppHandle, Manager, Runtime, Emitter};
 
-use crate::providers::ProviderRegistry;
-use crate::storage::CacheManager;
+use crate::config::{AppConfig, ConfigManager, Thresholds};
+use crate::providers::health::{HealthSnapshot, ProviderHealth};
+use crate::providers::retry::{RetryState, RetryStatus, RetryTracker};
+use crate::providers::{Provider, ProviderError, ProviderRegistry};
+use crate::storage::{vault, CacheManager, UsageData};
 use crate::notifications;
+use crate::tray;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How often the vault is checked for idle auto-lock
+const VAULT_AUTO_LOCK_CHECK: Duration = Duration::from_secs(30);
+
+/// How often the loop checks which providers are due
+const SCHEDULER_TICK: Duration = Duration::from_secs(5);
+
+/// Most provider refreshes allowed in flight at once
+const MAX_CONCURRENT_REFRESHES: usize = 3;
+
+/// A refresh taking longer than this counts as a network failure
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Windows resetting within this long count as "close to reset" in adaptive mode
+const ADAPTIVE_RESET_WINDOW: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Unchanged polls are doubled at most this many times
+const ADAPTIVE_MAX_BACKOFF_STEPS: u32 = 2;
+
+/// Backoff never stretches an interval beyond this (unless the base is longer)
+const ADAPTIVE_MAX_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// Delay after a known reset before polling, so the provider has caught up
+const RESET_REFRESH_DELAY: chrono::Duration = chrono::Duration::seconds(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum RefreshInterval {
+    #[serde(rename = "manual")]
     Manual,
+    #[serde(rename = "1m")]
     OneMinute,
+    #[serde(rename = "2m")]
     TwoMinutes,
+    #[serde(rename = "5m")]
     FiveMinutes,
+    #[serde(rename = "15m")]
     FifteenMinutes,
 }
 
@@ -29,6 +78,16 @@ impl RefreshInterval {
         }
     }
     
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RefreshInterval::Manual => "manual",
+            RefreshInterval::OneMinute => "1m",
+            RefreshInterval::TwoMinutes => "2m",
+            RefreshInterval::FiveMinutes => "5m",
+            RefreshInterval::FifteenMinutes => "15m",
+        }
+    }
+    
     pub fn from_str(s: &str) -> Self {
         match s {
             "1m" => RefreshInterval::OneMinute,
@@ -40,119 +99,839 @@ impl RefreshInterval {
     }
 }
 
+/// Which quota window reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetWindow {
+    Session,
+    Weekly,
+    Model,
+}
+
+/// Payload of the `quota-reset` event
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct QuotaReset {
+    pub provider: String,
+    pub window: ResetWindow,
+    /// Set for `ResetWindow::Model`
+    pub model_id: Option<String>,
+}
+
+/// Polling state tracked per provider
+#[derive(Debug, Clone)]
+pub struct ProviderSchedule {
+    pub last_refresh: Instant,
+    /// Wall-clock time of the last completed poll
+    pub last_refreshed_at: Option<DateTime<Utc>>,
+    pub last_usage: Option<UsageData>,
+    /// Consecutive successful polls that returned the same usage
+    pub unchanged_streak: u32,
+    pub retry: RetryTracker,
+    pub health: ProviderHealth,
+    /// A refresh has been claimed and not yet recorded
+    pub in_flight: bool,
+    /// Refresh on the next wake regardless of interval or pause
+    pub forced: bool,
+}
+
+impl ProviderSchedule {
+    fn new() -> Self {
+        Self {
+            last_refresh: Instant::now(),
+            last_refreshed_at: None,
+            last_usage: None,
+            unchanged_streak: 0,
+            retry: RetryTracker::default(),
+            health: ProviderHealth::default(),
+            in_flight: false,
+            forced: false,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Scheduler {
     interval: Arc<RwLock<RefreshInterval>>,
+    provider_intervals: Arc<RwLock<HashMap<String, RefreshInterval>>>,
+    adaptive: Arc<RwLock<bool>>,
+    schedules: Arc<RwLock<HashMap<String, ProviderSchedule>>>,
+    refresh_permits: Arc<Semaphore>,
+    /// Wakes the loop before its tick elapses
+    wake: Arc<Notify>,
+    paused: Arc<RwLock<bool>>,
     running: Arc<RwLock<bool>>,
 }
 
+/// Schedule of one provider, for the frontend
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProviderScheduleStatus {
+    pub provider: String,
+    pub interval: RefreshInterval,
+    pub last_run: Option<String>,
+    pub next_run: Option<String>,
+    pub in_flight: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SchedulerStatus {
+    pub interval: RefreshInterval,
+    pub paused: bool,
+    pub adaptive: bool,
+    pub last_run: Option<String>,
+    pub next_run: Option<String>,
+    pub providers: Vec<ProviderScheduleStatus>,
+}
+
 impl Scheduler {
     pub fn new() -> Self {
+        Self::with_interval(RefreshInterval::FiveMinutes)
+    }
+    
+    pub fn with_interval(interval: RefreshInterval) -> Self {
+        Self::build(interval, HashMap::new(), false)
+    }
+    
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::build(config.refresh_interval, config.provider_intervals(), config.adaptive_polling)
+    }
+    
+    fn build(interval: RefreshInterval, provider_intervals: HashMap<String, RefreshInterval>, adaptive: bool) -> Self {
         Self {
-            interval: Arc::new(RwLock::new(RefreshInterval::FiveMinutes)),
+            interval: Arc::new(RwLock::new(interval)),
+            provider_intervals: Arc::new(RwLock::new(provider_intervals)),
+            adaptive: Arc::new(RwLock::new(adaptive)),
+            schedules: Arc::new(RwLock::new(HashMap::new())),
+            refresh_permits: Arc::new(Semaphore::new(MAX_CONCURRENT_REFRESHES)),
+            wake: Arc::new(Notify::new()),
+            paused: Arc::new(RwLock::new(false)),
             running: Arc::new(RwLock::new(true)),
         }
     }
     
+    pub async fn interval(&self) -> RefreshInterval {
+        *self.interval.read().await
+    }
+    
     pub async fn set_interval(&self, interval: RefreshInterval) {
         *self.interval.write().await = interval;
     }
     
+    pub async fn set_provider_intervals(&self, intervals: HashMap<String, RefreshInterval>) {
+        *self.provider_intervals.write().await = intervals;
+    }
+    
+    pub async fn set_adaptive(&self, adaptive: bool) {
+        *self.adaptive.write().await = adaptive;
+    }
+    
+    /// Configured interval for a provider (its override, else the global one)
+    pub async fn provider_interval(&self, provider: &str) -> RefreshInterval {
+        match self.provider_intervals.read().await.get(provider) {
+            Some(interval) => *interval,
+            None => *self.interval.read().await,
+        }
+    }
+    
+    pub async fn is_paused(&self) -> bool {
+        *self.paused.read().await
+    }
+    
+    /// Stop scheduled polling; explicit refreshes still run
+    pub async fn pause(&self) {
+        *self.paused.write().await = true;
+    }
+    
+    pub async fn resume(&self) {
+        *self.paused.write().await = false;
+        self.wake.notify_one();
+    }
+    
+    /// Refresh these providers as soon as possible, waking the loop. Callers pass
+    /// enabled providers only; others would stay flagged without being claimed.
+    pub async fn force_refresh(&self, providers: impl IntoIterator<Item = String>) {
+        {
+            let mut schedules = self.schedules.write().await;
+            for provider in providers {
+                schedules.entry(provider).or_insert_with(ProviderSchedule::new).forced = true;
+            }
+        }
+        self.wake.notify_one();
+    }
+    
+    /// Claim a provider for refresh if it is due and not already in flight
+    async fn claim_if_due(&self, provider: &str, min_interval: Duration, thresholds: &Thresholds) -> bool {
+        let base = self.provider_interval(provider).await.to_duration();
+        let adaptive = *self.adaptive.read().await;
+        let paused = self.is_paused().await;
+        
+        let now = Utc::now();
+        let mut schedules = self.schedules.write().await;
+        let schedule = schedules.entry(provider.to_string()).or_insert_with(ProviderSchedule::new);
+        if schedule.in_flight {
+            return false;
+        }
+        
+        let due = if schedule.forced {
+            schedule.forced = false;
+            forced_claim_error(schedule, now).is_none()
+        } else {
+            !paused
+                && time_until_due(schedule, base, min_interval, adaptive, thresholds, now)
+                    .is_some_and(|d| d.is_zero())
+        };
+        schedule.in_flight = due;
+        due
+    }
+    
+    /// Claim a provider for a refresh the caller waits on; the rules of
+    /// [`force_refresh`](Self::force_refresh) apply
+    async fn claim_now(&self, provider: &str) -> Result<(), ProviderError> {
+        let mut schedules = self.schedules.write().await;
+        let schedule = schedules.entry(provider.to_string()).or_insert_with(ProviderSchedule::new);
+        if schedule.in_flight {
+            return Err(ProviderError::Provider(format!("{} is already refreshing", provider)));
+        }
+        if let Some(e) = forced_claim_error(schedule, Utc::now()) {
+            return Err(e);
+        }
+        schedule.forced = false;
+        schedule.in_flight = true;
+        Ok(())
+    }
+    
+    /// Last and next run times for the given providers and their minimum intervals
+    pub async fn status(&self, min_intervals: &[(String, Duration)], thresholds: &Thresholds) -> SchedulerStatus {
+        let interval = *self.interval.read().await;
+        let adaptive = *self.adaptive.read().await;
+        let paused = self.is_paused().await;
+        let now = Utc::now();
+        
+        let mut providers = Vec::new();
+        for (provider, min_interval) in min_intervals {
+            let provider_interval = self.provider_interval(provider).await;
+            let schedules = self.schedules.read().await;
+            let (last_run, next_run, in_flight) = match schedules.get(provider) {
+                Some(schedule) => {
+                    let next = if paused {
+                        None
+                    } else {
+                        time_until_due(schedule, provider_interval.to_duration(), *min_interval, adaptive, thresholds, now)
+                            .and_then(|d| chrono::Duration::from_std(d).ok())
+                            .map(|d| now + d)
+                    };
+                    (schedule.last_refreshed_at, next, schedule.in_flight)
+                }
+                None => (None, None, false),
+            };
+            providers.push((provider.clone(), provider_interval, last_run, next_run, in_flight));
+        }
+        
+        SchedulerStatus {
+            interval,
+            paused,
+            adaptive,
+            last_run: providers.iter().filter_map(|p| p.2).max().map(|t| t.to_rfc3339()),
+            next_run: providers.iter().filter_map(|p| p.3).min().map(|t| t.to_rfc3339()),
+            providers: providers
+                .into_iter()
+                .map(|(provider, interval, last_run, next_run, in_flight)| ProviderScheduleStatus {
+                    provider,
+                    interval,
+                    last_run: last_run.map(|t| t.to_rfc3339()),
+                    next_run: next_run.map(|t| t.to_rfc3339()),
+                    in_flight,
+                })
+                .collect(),
+        }
+    }
+    
+    /// Record a poll skipped without contacting the provider (locked vault)
+    async fn record_attempt(&self, provider: &str) {
+        let mut schedules = self.schedules.write().await;
+        let schedule = schedules.entry(provider.to_string()).or_insert_with(ProviderSchedule::new);
+        schedule.last_refresh = Instant::now();
+        schedule.in_flight = false;
+    }
+    
+    /// Record a failed poll and return the resulting retry state
+    pub async fn record_failure(&self, provider: &str, error: &ProviderError, latency: Duration) -> RetryState {
+        let mut schedules = self.schedules.write().await;
+        let schedule = schedules.entry(provider.to_string()).or_insert_with(ProviderSchedule::new);
+        schedule.last_refresh = Instant::now();
+        schedule.in_flight = false;
+        schedule.last_refreshed_at = Some(Utc::now());
+        schedule.retry.record_failure(error, Utc::now());
+        schedule.health.record_failure(error, latency);
+        schedule.retry.state()
+    }
+    
+    /// Record a successful poll and return any resets it observed
+    pub async fn record_usage(&self, provider: &str, usage: &UsageData, latency: Duration) -> Vec<QuotaReset> {
+        let mut schedules = self.schedules.write().await;
+        let schedule = schedules.entry(provider.to_string()).or_insert_with(ProviderSchedule::new);
+        schedule.last_refresh = Instant::now();
+        schedule.in_flight = false;
+        schedule.last_refreshed_at = Some(Utc::now());
+        schedule.retry.record_success();
+        schedule.health.record_success(latency);
+        schedule.unchanged_streak = match &schedule.last_usage {
+            Some(previous) if !usage_changed(previous, usage) => schedule.unchanged_streak + 1,
+            _ => 0,
+        };
+        let resets = match &schedule.last_usage {
+            Some(previous) => detect_resets(provider, previous, usage, Utc::now()),
+            None => Vec::new(),
+        };
+        schedule.last_usage = Some(usage.clone());
+        resets
+    }
+    
+    pub async fn health(&self, provider: &str) -> Option<HealthSnapshot> {
+        self.schedules.read().await.get(provider).map(|s| s.health.snapshot())
+    }
+    
+    pub async fn retry_status(&self, provider: &str) -> Option<RetryStatus> {
+        self.schedules.read().await.get(provider).map(|s| s.retry.status())
+    }
+    
+    /// Resume polling a provider that stopped on an auth error
+    pub async fn clear_auth(&self, provider: &str) {
+        if let Some(schedule) = self.schedules.write().await.get_mut(provider) {
+            schedule.retry.clear_auth();
+        }
+    }
+    
+    /// Resume polling every provider stopped on an auth error (e.g. after a vault unlock)
+    pub async fn clear_all_auth(&self) {
+        for schedule in self.schedules.write().await.values_mut() {
+            schedule.retry.clear_auth();
+        }
+    }
+    
     pub async fn stop(&self) {
         *self.running.write().await = false;
+        self.wake.notify_one();
     }
 }
 
-pub async fn start<R: Runtime>(app: AppHandle<R>) {
-    let scheduler = Scheduler::new();
+/// Why an explicit refresh may not run now: explicit requests skip the interval
+/// and backoff, but not Retry-After or auth stops
+fn forced_claim_error(schedule: &ProviderSchedule, now: DateTime<Utc>) -> Option<ProviderError> {
+    match schedule.retry.state() {
+        RetryState::AuthRequired => Some(ProviderError::AuthRequired),
+        RetryState::RateLimited if !schedule.retry.can_attempt(now) => {
+            let wait = schedule.retry.next_attempt().map_or(0, |at| (at - now).num_seconds().max(0));
+            Some(ProviderError::RateLimited(wait as u64))
+        }
+        _ => None,
+    }
+}
+
+/// Time until a provider's next scheduled poll; `None` when it isn't scheduled
+fn time_until_due(
+    schedule: &ProviderSchedule,
+    base: Option<Duration>,
+    min_interval: Duration,
+    adaptive: bool,
+    thresholds: &Thresholds,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    let remaining = |delay: Duration| delay.saturating_sub(schedule.last_refresh.elapsed());
+    if schedule.retry.state() == RetryState::AuthRequired {
+        return None;
+    }
+    if let Some(at) = schedule.retry.next_attempt() {
+        // A deferred retry runs once allowed, but never faster than the provider minimum
+        let wait = (at - now).to_std().unwrap_or_default();
+        return Some(wait.max(remaining(min_interval)));
+    }
+    let scheduled = base.map(|base| remaining(next_delay(base, min_interval, adaptive, schedule, thresholds, now)));
+    let after_reset = reset_refresh_at(schedule)
+        .map(|at| (at - now).to_std().unwrap_or_default().max(remaining(min_interval)));
+    scheduled.into_iter().chain(after_reset).min()
+}
+
+/// When to poll to observe the earliest reset that falls after the last poll
+fn reset_refresh_at(schedule: &ProviderSchedule) -> Option<DateTime<Utc>> {
+    let usage = schedule.last_usage.as_ref()?;
+    let polled_at = schedule.last_refreshed_at?;
+    reset_times(usage)
+        .filter(|t| *t > polled_at)
+        .min()
+        .map(|t| t + RESET_REFRESH_DELAY)
+}
+
+fn reset_times(usage: &UsageData) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+    usage
+        .reset_time
+        .into_iter()
+        .chain(usage.weekly_reset_time)
+        .chain(usage.model_quotas.iter().flatten().filter_map(|q| q.reset_time))
+}
+
+/// Windows whose reset time had passed and whose new data shows the reset
+fn detect_resets(provider: &str, previous: &UsageData, current: &UsageData, now: DateTime<Utc>) -> Vec<QuotaReset> {
+    let reset = |window, model_id: Option<&str>| QuotaReset {
+        provider: provider.to_string(),
+        window,
+        model_id: model_id.map(str::to_string),
+    };
+    let passed = |t: Option<DateTime<Utc>>| t.is_some_and(|t| t <= now);
+    let mut resets = Vec::new();
     
-    // Store scheduler in app state
-    app.manage(Arc::new(scheduler.clone())); // Store Arc<Scheduler> directly? No, manage handles T. 
-                                            // The code in PHASE-1-CORE uses Arc<Scheduler> but the method signature of manage is manage<T>(state: T).
-                                            // Wait, `app.manage` takes T. If I pass Arc<Scheduler>, retrieving it needs to be app.state::<Arc<Scheduler>>().
-                                            // The code below uses app.manage(Arc::new(scheduler)). 
-                                            // Then in loop it accesses scheduler directly. 
-                                            // To access from other commands, we'll need to know the type.
-                                            // Let's assume Arc<Scheduler> is the intended state type.
+    if passed(previous.reset_time)
+        && (current.reset_time != previous.reset_time || current.session_used < previous.session_used)
+    {
+        resets.push(reset(ResetWindow::Session, None));
+    }
+    if passed(previous.weekly_reset_time)
+        && (current.weekly_reset_time != previous.weekly_reset_time || current.weekly_used < previous.weekly_used)
+    {
+        resets.push(reset(ResetWindow::Weekly, None));
+    }
+    for before in previous.model_quotas.iter().flatten() {
+        let after = current.model_quotas.iter().flatten().find(|q| q.model_id == before.model_id);
+        if let Some(after) = after {
+            if passed(before.reset_time) && (after.reset_time != before.reset_time || after.percent_left > before.percent_left) {
+                resets.push(reset(ResetWindow::Model, Some(&before.model_id)));
+            }
+        }
+    }
+    resets
+}
+
+/// Run the refresh loop. Expects `Arc<Scheduler>` to be managed during setup so
+/// commands can reach it before the loop starts.
+pub async fn start<R: Runtime>(app: AppHandle<R>) {
+    let scheduler = app.state::<Arc<Scheduler>>().inner().clone();
     
     // Main refresh loop
     loop {
-        let current_interval = *scheduler.interval.read().await;
-        
         if !*scheduler.running.read().await {
             break;
         }
         
-        if let Some(duration) = current_interval.to_duration() {
-            // Wait for interval
-            tokio::time::sleep(duration).await;
-            
-            // Refresh all enabled providers
-            refresh_all_providers(&app).await;
-        } else {
-            // Manual mode: just sleep and check again
-            tokio::time::sleep(Duration::from_secs(10)).await;
+        tokio::select! {
+            _ = tokio::time::sleep(SCHEDULER_TICK) => {}
+            _ = scheduler.wake.notified() => {}
         }
+        refresh_due_providers(&app, &scheduler).await;
     }
 }
 
-async fn refresh_all_providers<R: Runtime>(app: &AppHandle<R>) {
-    // Get provider registry from state
-    if let Some(registry) = app.try_state::<Arc<RwLock<ProviderRegistry>>>() {
+/// Refresh one provider (or every enabled one) now instead of waiting for its turn.
+/// Fails for a provider that doesn't exist or is disabled.
+pub async fn refresh_now<R: Runtime>(app: &AppHandle<R>, provider: Option<String>) -> Result<(), String> {
+    let (Some(scheduler), Some(registry)) = (
+        app.try_state::<Arc<Scheduler>>(),
+        app.try_state::<Arc<RwLock<ProviderRegistry>>>(),
+    ) else {
+        return Err("Scheduler is not running".to_string());
+    };
+    let (known, enabled): (Vec<String>, Vec<String>) = {
         let registry = registry.read().await;
+        let enabled = registry.enabled_providers().into_iter().map(|(name, _)| name).collect();
+        (registry.all_provider_names(), enabled)
+    };
+    let providers = match provider {
+        Some(provider) if !known.contains(&provider) => return Err(format!("Provider '{}' not found", provider)),
+        Some(provider) if !enabled.contains(&provider) => return Err(format!("Provider '{}' is disabled", provider)),
+        Some(provider) => vec![provider],
+        None => enabled,
+    };
+    scheduler.force_refresh(providers).await;
+    Ok(())
+}
+
+async fn refresh_due_providers<R: Runtime>(app: &AppHandle<R>, scheduler: &Scheduler) {
+    let thresholds = current_thresholds(app).await;
+    
+    // Snapshot the registry so the lock isn't held across refreshes
+    let providers = match app.try_state::<Arc<RwLock<ProviderRegistry>>>() {
+        Some(registry) => registry.read().await.enabled_providers(),
+        None => return,
+    };
+    
+    for (name, provider_arc) in providers {
+        // A provider busy with an auth flow is skipped rather than waited on
+        let Ok(min_interval) = provider_arc.try_read().map(|p| p.min_refresh_interval()) else {
+            continue;
+        };
+        if !scheduler.claim_if_due(&name, min_interval, &thresholds).await {
+            continue;
+        }
         
-        for (name, provider_arc) in registry.enabled_providers() {
-            let provider = provider_arc.read().await;
-            match provider.fetch_usage().await {
-                Ok(usage) => {
-                    // Update cache
-                    if let Some(cache) = app.try_state::<Arc<RwLock<CacheManager>>>() {
-                        let mut cache = cache.write().await;
-                        cache.set(name.as_str(), usage.clone());
-                        let _ = cache.save();
-                    }
-                    
-                    // Check for low usage warnings
-                    check_usage_warnings(app, &name, &usage).await;
-                    
-                    // Emit update event
-                    let _ = app.emit("provider-updated", (name.as_str(), &usage));
-                }
-                Err(e) => {
-                    log::error!("Failed to refresh {}: {}", name, e);
-                    let _ = app.emit("provider-error", (name.as_str(), e.to_string()));
-                }
-            }
+        // Credentials in a locked vault are unavailable by design, not a failure
+        if vault::is_provider_locked(&name) {
+            log::debug!("Skipping {}: credentials are in the locked vault", name);
+            scheduler.record_attempt(&name).await;
+            let _ = app.emit("provider-auth-required", (name.as_str(), ProviderError::AuthRequired.to_string()));
+            continue;
+        }
+        
+        // Runs detached; the claim keeps later ticks from starting it again
+        let app = app.clone();
+        let scheduler = scheduler.clone();
+        tauri::async_runtime::spawn(async move {
+            refresh_claimed(&app, &scheduler, &name, provider_arc).await;
+        });
+    }
+}
+
+/// Refresh one enabled provider and wait for the result. Shares the scheduler's
+/// claim, so it never overlaps a scheduled fetch of the same provider.
+pub async fn refresh_provider_now<R: Runtime>(app: &AppHandle<R>, provider: &str) -> Result<UsageData, String> {
+    let (Some(scheduler), Some(registry)) = (
+        app.try_state::<Arc<Scheduler>>(),
+        app.try_state::<Arc<RwLock<ProviderRegistry>>>(),
+    ) else {
+        return Err("Scheduler is not running".to_string());
+    };
+    let provider_arc = {
+        let registry = registry.read().await;
+        match registry.get_provider(provider) {
+            None => return Err(format!("Provider '{}' not found", provider)),
+            Some(_) if !registry.is_enabled(provider) => return Err(format!("Provider '{}' is disabled", provider)),
+            Some(provider_arc) => provider_arc,
+        }
+    };
+    if vault::is_provider_locked(provider) {
+        return Err(ProviderError::AuthRequired.to_string());
+    }
+    scheduler.claim_now(provider).await.map_err(|e| e.to_string())?;
+    refresh_claimed(app, &scheduler, provider, provider_arc)
+        .await
+        .ok_or_else(|| "Scheduler is not running".to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Fetch a claimed provider under the refresh timeout and record the outcome;
+/// `None` if the scheduler shut down before the fetch could start
+async fn refresh_claimed<R: Runtime>(
+    app: &AppHandle<R>,
+    scheduler: &Scheduler,
+    name: &str,
+    provider_arc: Arc<RwLock<dyn Provider>>,
+) -> Option<Result<UsageData, ProviderError>> {
+    let mut claim = ClaimGuard::new(scheduler, name);
+    let Ok(_permit) = scheduler.refresh_permits.clone().acquire_owned().await else {
+        return None;
+    };
+    let started = Instant::now();
+    // The timeout also covers waiting out an auth flow's write lock
+    let refresh = async {
+        let provider = provider_arc.read().await;
+        (provider.fetch_usage().await, provider.credentials_expire_at())
+    };
+    let (result, credentials_expire_at) = match tokio::time::timeout(REFRESH_TIMEOUT, refresh).await {
+        Ok(refreshed) => refreshed,
+        Err(_) => (
+            Err(ProviderError::Network(format!("timed out after {}s", REFRESH_TIMEOUT.as_secs()))),
+            None,
+        ),
+    };
+    handle_refresh_result(app, scheduler, name, &result, started.elapsed()).await;
+    claim.disarm();
+    notifications::auth::check_credential_expiry(app, name, credentials_expire_at).await;
+    Some(result)
+}
+
+/// Releases a provider's refresh claim if its task ends without recording a result,
+/// e.g. on panic, so the provider isn't left in flight forever
+struct ClaimGuard {
+    scheduler: Option<Scheduler>,
+    provider: String,
+}
+
+impl ClaimGuard {
+    fn new(scheduler: &Scheduler, provider: &str) -> Self {
+        Self {
+            scheduler: Some(scheduler.clone()),
+            provider: provider.to_string(),
         }
     }
+
+    /// The result was recorded, which already released the claim
+    fn disarm(&mut self) {
+        self.scheduler = None;
+    }
 }
 
-async fn check_usage_warnings<R: Runtime>(
+impl Drop for ClaimGuard {
+    fn drop(&mut self) {
+        if let Some(scheduler) = self.scheduler.take() {
+            let provider = std::mem::take(&mut self.provider);
+            tauri::async_runtime::spawn(async move {
+                scheduler.record_attempt(&provider).await;
+            });
+        }
+    }
+}
+
+async fn handle_refresh_result<R: Runtime>(
     app: &AppHandle<R>,
-    provider: &str,
-    usage: &crate::storage::UsageData,
+    scheduler: &Scheduler,
+    name: &str,
+    result: &Result<UsageData, ProviderError>,
+    latency: Duration,
 ) {
-    // Check if session usage is above 80%
-    if usage.session_limit > 0 {
-        let percent = (usage.session_used as f64 / usage.session_limit as f64) * 100.0;
-        if percent >= 80.0 {
-            notifications::send_warning(
-                app,
-                &format!("{} Usage Warning", provider),
-                &format!("Session usage at {:.0}% ({}/{})", percent, usage.session_used, usage.session_limit),
-            ).await;
-        }
-    }
-    
-    // Check weekly usage
-    if usage.weekly_limit > 0 {
-        let percent = (usage.weekly_used as f64 / usage.weekly_limit as f64) * 100.0;
-        if percent >= 90.0 {
-            notifications::send_warning(
-                app,
-                &format!("{} Weekly Limit", provider),
-                &format!("Weekly usage at {:.0}%", percent),
-            ).await;
-        }
-    }
-}
\ No newline at end of file
+    match result {
+        Ok(usage) => {
+            for reset in scheduler.record_usage(name, usage, latency).await {
+                log::info!("{} quota reset ({:?})", name, reset.window);
+                let _ = app.emit("quota-reset", &reset);
+            }
+            
+            // Update cache
+            let mut previous = None;
+            if let Some(cache) = app.try_state::<Arc<RwLock<CacheManager>>>() {
+                let mut cache = cache.write().await;
+                previous = cache.get(name).cloned();
+                cache.set(name, usage.clone());
+                let _ = cache.save();
+            }
+            
+            notifications::auth::clear_auth_failure(app, name).await;
+            notifications::check_usage_alerts(app, name, previous.as_ref(), usage).await;
+            
+            // Emit update event
+            let _ = app.emit("provider-updated", (name, usage));
+        }
+        Err(e) => {
+            match scheduler.record_failure(name, e, latency).await {
+                RetryState::AuthRequired => {
+                    log::warn!("Stopped polling {} until it is re-authenticated", name);
+                    let _ = app.emit("provider-auth-required", (name, e.to_string()));
+                }
+                RetryState::CircuitOpen => log::warn!("Circuit open for {} after repeated failures", name),
+                _ => {}
+            }
+            log::error!("Failed to refresh {}: {}", name, e);
+            let _ = app.emit("provider-error", (name, e.to_string()));
+            notifications::auth::check_auth_failure(app, name, e).await;
+        }
+    }
+    tray::refresh(app).await;
+}
+
+/// Ask every provider to re-read its stored credentials
+pub async fn reload_provider_credentials<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(registry) = app.try_state::<Arc<RwLock<ProviderRegistry>>>() {
+        let providers: Vec<_> = {
+            let registry = registry.read().await;
+            registry
+                .all_provider_names()
+                .into_iter()
+                .filter_map(|name| registry.get_provider(&name))
+                .collect()
+        };
+        for provider in providers {
+            provider.write().await.reload_credentials().await;
+        }
+    }
+}
+
+/// Lock the vault after its idle timeout and drop credentials providers cached from it
+pub async fn run_vault_auto_lock<R: Runtime>(app: AppHandle<R>) {
+    loop {
+        tokio::time::sleep(VAULT_AUTO_LOCK_CHECK).await;
+        
+        let locked = vault::with_vault(|v| v.lock_if_idle()).unwrap_or(false);
+        if locked {
+            log::info!("Vault auto-locked after idle timeout");
+            reload_provider_credentials(&app).await;
+            let _ = app.emit("vault-locked", ());
+        }
+    }
+}
+
+/// Delay before the next poll of a provider, given its last observed usage
+pub fn next_delay(
+    base: Duration,
+    min_interval: Duration,
+    adaptive: bool,
+    schedule: &ProviderSchedule,
+    thresholds: &Thresholds,
+    now: DateTime<Utc>,
+) -> Duration {
+    let delay = match (&schedule.last_usage, adaptive) {
+        (Some(usage), true) if is_hot(usage, thresholds, now) => base / 2,
+        (Some(usage), true) => {
+            // Nothing used yet counts as idle even on the first poll
+            let idle = usage.session_used == 0 && usage.weekly_used == 0;
+            let steps = schedule.unchanged_streak.max(idle as u32).min(ADAPTIVE_MAX_BACKOFF_STEPS);
+            (base * 2u32.pow(steps)).min(ADAPTIVE_MAX_INTERVAL.max(base))
+        }
+        _ => base,
+    };
+    delay.max(min_interval)
+}
+
+/// A window is above its warning threshold or about to reset
+fn is_hot(usage: &UsageData, thresholds: &Thresholds, now: DateTime<Utc>) -> bool {
+    let over = |used: u64, limit: u64, threshold: f64| {
+        limit > 0 && (used as f64 / limit as f64) * 100.0 >= threshold
+    };
+    let resetting_soon = |reset: Option<DateTime<Utc>>| {
+        reset.is_some_and(|t| t > now && t - now <= ADAPTIVE_RESET_WINDOW)
+    };
+    
+    over(usage.session_used, usage.session_limit, thresholds.session_warning_percent)
+        || over(usage.weekly_used, usage.weekly_limit, thresholds.weekly_warning_percent)
+        || usage.model_quotas.iter().flatten().any(|q| {
+            100.0 - q.percent_left >= thresholds.session_warning_percent || resetting_soon(q.reset_time)
+        })
+        || resetting_soon(usage.reset_time)
+        || resetting_soon(usage.weekly_reset_time)
+}
+
+fn usage_changed(previous: &UsageData, current: &UsageData) -> bool {
+    let quotas = |u: &UsageData| -> Vec<(String, f64)> {
+        u.model_quotas
+            .iter()
+            .flatten()
+            .map(|q| (q.model_id.clone(), q.percent_left))
+            .collect()
+    };
+    
+    previous.session_used != current.session_used
+        || previous.weekly_used != current.weekly_used
+        || previous.credits_remaining != current.credits_remaining
+        || quotas(previous) != quotas(current)
+}
+
+async fn current_thresholds<R: Runtime>(app: &AppHandle<R>) -> Thresholds {
+    match app.try_state::<Arc<RwLock<ConfigManager>>>() {
+        Some(config) => config.read().await.get().thresholds.clone(),
+        None => Thresholds::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ModelQuota;
+
+    fn schedule(usage: UsageData, unchanged_streak: u32) -> ProviderSchedule {
+        ProviderSchedule {
+            last_refresh: Instant::now(),
+            last_usage: Some(usage),
+            unchanged_streak,
+            ..ProviderSchedule::new()
+        }
+    }
+
+    #[test]
+    fn test_adaptive_delay() {
+        let base = Duration::from_secs(300);
+        let min = Duration::from_secs(120);
+        let thresholds = Thresholds::default();
+        let now = Utc::now();
+        let busy = UsageData {
+            session_used: 10,
+            session_limit: 100,
+            ..Default::default()
+        };
+
+        // Disabled: always the base interval
+        assert_eq!(next_delay(base, min, false, &schedule(busy.clone(), 3), &thresholds, now), base);
+
+        // Unchanged usage backs off, capped at four times the base
+        assert_eq!(next_delay(base, min, true, &schedule(busy.clone(), 1), &thresholds, now), base * 2);
+        assert_eq!(next_delay(base, min, true, &schedule(busy.clone(), 5), &thresholds, now), base * 4);
+
+        // Near the limit or a reset polls faster, but never below the provider minimum
+        let hot = UsageData { session_used: 85, ..busy.clone() };
+        assert_eq!(next_delay(base, min, true, &schedule(hot, 0), &thresholds, now), Duration::from_secs(150));
+        let resetting = UsageData {
+            reset_time: Some(now + chrono::Duration::minutes(2)),
+            ..busy
+        };
+        let fast = Duration::from_secs(60);
+        assert_eq!(next_delay(fast, min, true, &schedule(resetting, 0), &thresholds, now), min);
+    }
+
+    #[test]
+    fn test_polls_just_after_known_reset() {
+        let now = Utc::now();
+        let usage = UsageData {
+            reset_time: Some(now + chrono::Duration::minutes(3)),
+            weekly_reset_time: Some(now + chrono::Duration::days(2)),
+            ..Default::default()
+        };
+        let mut schedule = schedule(usage, 0);
+        schedule.last_refreshed_at = Some(now);
+
+        let base = Some(Duration::from_secs(900));
+        let due = time_until_due(&schedule, base, Duration::from_secs(60), false, &Thresholds::default(), now).unwrap();
+        assert_eq!(due, Duration::from_secs(195));
+
+        // Manual mode still gets the one-off poll
+        assert!(time_until_due(&schedule, None, Duration::from_secs(60), false, &Thresholds::default(), now).is_some());
+    }
+
+    #[test]
+    fn test_detect_resets() {
+        let now = Utc::now();
+        let quota = |percent_left, reset_time| ModelQuota {
+            model_id: "pro".into(),
+            percent_left,
+            reset_time,
+        };
+        let previous = UsageData {
+            session_used: 90,
+            reset_time: Some(now - chrono::Duration::seconds(20)),
+            weekly_used: 300,
+            weekly_reset_time: Some(now + chrono::Duration::days(3)),
+            model_quotas: Some(vec![quota(5.0, Some(now - chrono::Duration::seconds(20)))]),
+            ..Default::default()
+        };
+        let current = UsageData {
+            session_used: 0,
+            reset_time: Some(now + chrono::Duration::hours(5)),
+            weekly_used: 310,
+            model_quotas: Some(vec![quota(100.0, Some(now + chrono::Duration::days(1)))]),
+            ..previous.clone()
+        };
+
+        let resets = detect_resets("gemini", &previous, &current, now);
+        let windows: Vec<_> = resets.iter().map(|r| r.window).collect();
+        assert_eq!(windows, vec![ResetWindow::Session, ResetWindow::Model]);
+        assert_eq!(resets[1].model_id.as_deref(), Some("pro"));
+        assert!(detect_resets("gemini", &current, &current, now).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_claim_is_released() {
+        let scheduler = Scheduler::new();
+        scheduler.force_refresh(["claude".to_string()]).await;
+        let min = Duration::from_secs(60);
+        assert!(scheduler.claim_if_due("claude", min, &Thresholds::default()).await);
+
+        // A task that dies before recording a result
+        drop(ClaimGuard::new(&scheduler, "claude"));
+        for _ in 0..50 {
+            if !scheduler.schedules.read().await["claude"].in_flight {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("claim was not released");
+    }
+
+    #[tokio::test]
+    async fn test_manual_claim_shares_in_flight() {
+        let scheduler = Scheduler::new();
+        scheduler.claim_now("claude").await.unwrap();
+        assert!(scheduler.claim_now("claude").await.is_err());
+
+        // A forced tick must not start a second fetch of the claimed provider
+        scheduler.force_refresh(["claude".to_string()]).await;
+        let min = Duration::from_secs(60);
+        assert!(!scheduler.claim_if_due("claude", min, &Thresholds::default()).await);
+
+        let limited = ProviderError::RateLimited(120);
+        scheduler.record_failure("claude", &limited, Duration::ZERO).await;
+        assert!(matches!(scheduler.claim_now("claude").await, Err(ProviderError::RateLimited(secs)) if secs > 0));
+    }
+}
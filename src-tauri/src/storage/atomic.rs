@@ -0,0 +1,115 @@
+//! Crash-safe file persistence helpers shared by the storage layer
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Sibling path used while a write is in flight
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "data".to_string());
+    path.with_file_name(format!("{}.tmp", file_name))
+}
+
+/// Write a file atomically: write to a temp file, fsync, then rename over the target.
+///
+/// Readers either see the previous contents or the new contents, never a partial write.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp = temp_path(path);
+    {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+    }
+
+    if let Err(e) = fs::rename(&temp, path) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Move an unreadable file aside so it can be inspected later instead of being overwritten.
+///
+/// Returns the quarantine path, e.g. `usage_cache.json.corrupt-20250101T120000Z`.
+pub fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "data".to_string());
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let target = path.with_file_name(format!("{}.corrupt-{}", file_name, stamp));
+
+    // Rename is cheapest; fall back to copy when crossing filesystems
+    if fs::rename(path, &target).is_err() {
+        fs::copy(path, &target)?;
+    }
+    Ok(target)
+}
+
+/// Result of loading a state file with [`load_json`] or [`load_with`]
+#[derive(Debug)]
+pub enum Loaded<T> {
+    Found(T),
+    /// No file exists yet
+    Missing,
+    /// The file was corrupt and has been moved aside
+    Quarantined,
+    /// The file couldn't be read or parsed but may still be good: it was left
+    /// in place and must not be overwritten
+    Kept,
+}
+
+/// Why a file's contents were rejected by a [`load_with`] parser
+pub enum Rejected<E> {
+    /// Damaged beyond use; quarantine it
+    Corrupt(E),
+    /// Possibly fine for another build or later retry; leave it in place
+    Keep(E),
+}
+
+/// Load a JSON file, quarantining it if it doesn't parse
+pub fn load_json<T: DeserializeOwned>(path: &Path, what: &str) -> Loaded<T> {
+    load_with(path, what, |contents| serde_json::from_str(contents).map_err(Rejected::Corrupt))
+}
+
+/// Load a file through `parse`. Only a missing file counts as [`Loaded::Missing`];
+/// read errors keep the file, and `parse` decides between quarantining and keeping.
+pub fn load_with<T, E: Display>(
+    path: &Path,
+    what: &str,
+    parse: impl FnOnce(&str) -> Result<T, Rejected<E>>,
+) -> Loaded<T> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Loaded::Missing,
+        Err(e) => {
+            log::warn!("Can't load {} at {}, leaving it in place: {}", what, path.display(), e);
+            return Loaded::Kept;
+        }
+    };
+    match parse(&contents) {
+        Ok(value) => Loaded::Found(value),
+        Err(Rejected::Corrupt(e)) => {
+            log::warn!("The {} at {} is corrupt: {}", what, path.display(), e);
+            match quarantine(path) {
+                Ok(target) => log::warn!("Quarantined the {} to {}", what, target.display()),
+                Err(e) => log::error!("Failed to quarantine {}: {}", what, e),
+            }
+            Loaded::Quarantined
+        }
+        Err(Rejected::Keep(e)) => {
+            log::warn!("Can't load {} at {}, leaving it in place: {}", what, path.display(), e);
+            Loaded::Kept
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Passphrase-encrypted backup archives for moving LimitWatcher between machines
+//!
+//! An archive is a small JSON header plus one encrypted payload holding provider
+//! enablement, settings, the usage cache and (optionally) stored credentials.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config::AppConfig;
+use crate::notifications::sinks;
+
+use super::atomic;
+use super::cache::UsageCache;
+use super::encrypted::{self, EncryptedBlob, EncryptedStorageError, KdfParams, KeyMaterial};
+
+const BACKUP_FORMAT: &str = "limitswatcher-backup";
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Archives leave the machine, so use a stronger KDF than machine-bound files
+const BACKUP_KDF_PARAMS: KdfParams = KdfParams {
+    m_cost: 64 * 1024,
+    t_cost: 3,
+    p_cost: 1,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("Not a LimitWatcher backup")]
+    InvalidFormat,
+    #[error("Backup version {0} is newer than this app supports")]
+    UnsupportedVersion(u32),
+    #[error("Incorrect passphrase or corrupted backup")]
+    WrongPassphrase,
+    #[error("Passphrase must not be empty")]
+    EmptyPassphrase,
+    #[error("Encryption error: {0}")]
+    Encrypted(#[from] EncryptedStorageError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, BackupError>;
+
+/// Everything restored by an import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupContents {
+    pub created_at: DateTime<Utc>,
+    pub app_version: String,
+    #[serde(default)]
+    pub provider_enabled: HashMap<String, bool>,
+    /// Application settings (interval, thresholds, ordering)
+    #[serde(default)]
+    pub config: Option<AppConfig>,
+    #[serde(default)]
+    pub cache: UsageCache,
+    /// `None` when the backup was exported without secrets
+    #[serde(default)]
+    pub credentials: Option<HashMap<String, String>>,
+}
+
+/// Unencrypted archive header, readable without the passphrase
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupArchive {
+    format: String,
+    version: u32,
+    created_at: DateTime<Utc>,
+    includes_secrets: bool,
+    payload: EncryptedBlob,
+}
+
+/// What an archive contains, for confirming an import
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupSummary {
+    pub created_at: String,
+    pub app_version: String,
+    pub providers: Vec<String>,
+    pub cached_providers: usize,
+    pub credential_count: usize,
+    pub includes_secrets: bool,
+}
+
+impl BackupContents {
+    /// Drop credentials and the URLs and tokens of notification sinks
+    pub fn strip_secrets(&mut self) {
+        self.credentials = None;
+        if let Some(config) = &mut self.config {
+            sinks::redact_secrets(&mut config.notification_sinks);
+        }
+    }
+
+    pub fn summary(&self) -> BackupSummary {
+        let mut providers: Vec<String> = self.provider_enabled.keys().cloned().collect();
+        providers.sort();
+        BackupSummary {
+            created_at: self.created_at.to_rfc3339(),
+            app_version: self.app_version.clone(),
+            providers,
+            cached_providers: self.cache.providers.len(),
+            credential_count: self.credentials.as_ref().map(|c| c.len()).unwrap_or(0),
+            includes_secrets: self.credentials.is_some(),
+        }
+    }
+}
+
+/// Encrypt and write a backup archive
+pub fn write_backup(path: &Path, contents: &BackupContents, passphrase: &str) -> Result<()> {
+    write_backup_with(path, contents, passphrase, BACKUP_KDF_PARAMS)
+}
+
+fn write_backup_with(path: &Path, contents: &BackupContents, passphrase: &str, params: KdfParams) -> Result<()> {
+    if passphrase.is_empty() {
+        return Err(BackupError::EmptyPassphrase);
+    }
+
+    let archive = BackupArchive {
+        format: BACKUP_FORMAT.to_string(),
+        version: BACKUP_FORMAT_VERSION,
+        created_at: contents.created_at,
+        includes_secrets: contents.credentials.is_some(),
+        payload: encrypted::encrypt_blob(contents, KeyMaterial::Passphrase(passphrase), params)?,
+    };
+    atomic::write_atomic(path, serde_json::to_string_pretty(&archive)?)?;
+    Ok(())
+}
+
+/// Read, validate and decrypt a backup archive
+pub fn read_backup(path: &Path, passphrase: &str) -> Result<BackupContents> {
+    let json = fs::read_to_string(path)?;
+    let archive: BackupArchive = serde_json::from_str(&json).map_err(|_| BackupError::InvalidFormat)?;
+
+    if archive.format != BACKUP_FORMAT {
+        return Err(BackupError::InvalidFormat);
+    }
+    if archive.version > BACKUP_FORMAT_VERSION {
+        return Err(BackupError::UnsupportedVersion(archive.version));
+    }
+
+    let contents: BackupContents = encrypted::decrypt_blob(&archive.payload, KeyMaterial::Passphrase(passphrase))
+        .map_err(|e| match e {
+            EncryptedStorageError::Decryption => BackupError::WrongPassphrase,
+            other => other.into(),
+        })?;
+
+    // The clear header must agree with the encrypted payload
+    if archive.includes_secrets != contents.credentials.is_some() {
+        return Err(BackupError::InvalidFormat);
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_roundtrip_and_validation() {
+        let path = std::env::temp_dir().join(format!("limitswatcher-backup-{}.json", std::process::id()));
+        let contents = BackupContents {
+            created_at: Utc::now(),
+            app_version: "0.1.0".into(),
+            provider_enabled: HashMap::from([("copilot".to_string(), true)]),
+            config: Some(AppConfig::default()),
+            cache: UsageCache::default(),
+            credentials: Some(HashMap::from([("copilot_access_token".to_string(), "gho_1".to_string())])),
+        };
+        let cheap = KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+        write_backup_with(&path, &contents, "hunter2", cheap).unwrap();
+
+        let restored = read_backup(&path, "hunter2").unwrap();
+        assert_eq!(restored.summary().credential_count, 1);
+        assert_eq!(restored.provider_enabled.get("copilot"), Some(&true));
+        assert!(matches!(read_backup(&path, "nope"), Err(BackupError::WrongPassphrase)));
+
+        fs::write(&path, r#"{"hello":"world"}"#).unwrap();
+        assert!(matches!(read_backup(&path, "hunter2"), Err(BackupError::InvalidFormat)));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_strip_secrets_redacts_sinks() {
+        let path = std::env::temp_dir().join(format!("limitswatcher-backup-nosecrets-{}.json", std::process::id()));
+        let config = AppConfig {
+            notification_sinks: serde_json::from_str(
+                r#"[{"id":"team","target":{"type":"chat","url":"https://hooks.slack.com/services/T0/B0/secret","flavor":"slack"}},
+                    {"id":"phone","target":{"type":"gotify","url":"https://push.example.com","token":"gotify-token"}}]"#,
+            )
+            .unwrap(),
+            ..AppConfig::default()
+        };
+        let mut contents = BackupContents {
+            created_at: Utc::now(),
+            app_version: "0.1.0".into(),
+            provider_enabled: HashMap::new(),
+            config: Some(config),
+            cache: UsageCache::default(),
+            credentials: Some(HashMap::from([("copilot_access_token".to_string(), "gho_1".to_string())])),
+        };
+        contents.strip_secrets();
+        let cheap = KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+        write_backup_with(&path, &contents, "hunter2", cheap).unwrap();
+
+        let restored = read_backup(&path, "hunter2").unwrap();
+        let payload = serde_json::to_string(&restored).unwrap();
+        for secret in ["hooks.slack.com", "gotify-token", "gho_1"] {
+            assert!(!payload.contains(secret), "{} leaked into the backup", secret);
+        }
+        let sinks = &restored.config.unwrap().notification_sinks;
+        assert_eq!(sinks[0].secret_key.as_deref(), Some("notification_sink.team"));
+        assert!(sinks.iter().all(|sink| sink.validate().is_ok()));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,11 +1,33 @@
 //! Non-sensitive usage data cache for offline display
+//!
+//! The cache file is wrapped in a versioned envelope and written atomically.
+//! Older layouts are migrated on load; corrupt files are quarantined. A file
+//! that can't be read for other reasons, or comes from a newer build, is left
+//! untouched and not overwritten.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
 use std::path::PathBuf;
 use chrono::{DateTime, Utc};
 
+use super::atomic;
+
+/// Current on-disk schema version of `usage_cache.json`
+///
+/// - v1: bare `UsageCache` object (no envelope)
+/// - v2: `{ version, saved_at, cache }` envelope
+pub const CACHE_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("Unsupported cache schema version {0} (this build supports up to {CACHE_SCHEMA_VERSION})")]
+    UnsupportedVersion(u32),
+}
+
+pub type Result<T> = std::result::Result<T, CacheError>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UsageData {
     pub session_used: u64,
@@ -50,31 +72,106 @@ pub struct UsageCache {
     pub providers: HashMap<String, UsageData>,
 }
 
+/// Versioned wrapper persisted to disk
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEnvelope {
+    version: u32,
+    saved_at: DateTime<Utc>,
+    cache: UsageCache,
+}
+
+/// Parse cache file contents of any supported schema version
+fn parse_cache(contents: &str) -> Result<(UsageCache, u32)> {
+    let mut value: serde_json::Value = serde_json::from_str(contents)?;
+
+    // Files written before the envelope existed carry no version field
+    let original_version = value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .unwrap_or(1);
+
+    if original_version > CACHE_SCHEMA_VERSION {
+        return Err(CacheError::UnsupportedVersion(original_version));
+    }
+
+    let mut version = original_version;
+    while version < CACHE_SCHEMA_VERSION {
+        value = migrate(version, value)?;
+        version += 1;
+    }
+
+    let envelope: CacheEnvelope = serde_json::from_value(value)?;
+    Ok((envelope.cache, original_version))
+}
+
+/// Upgrade a cache document from `from_version` to `from_version + 1`
+fn migrate(from_version: u32, value: serde_json::Value) -> Result<serde_json::Value> {
+    match from_version {
+        // v1 -> v2: wrap the bare cache in an envelope
+        1 => Ok(serde_json::json!({
+            "version": 2,
+            "saved_at": Utc::now(),
+            "cache": value,
+        })),
+        other => Err(CacheError::UnsupportedVersion(other)),
+    }
+}
+
 pub struct CacheManager {
     path: PathBuf,
     cache: UsageCache,
+    /// Entries last updated before this are stale (set after resume/network changes)
+    stale_since: Option<DateTime<Utc>>,
+    /// The file on disk couldn't be loaded but may still be good; don't overwrite it
+    keep_file: bool,
 }
 
 impl CacheManager {
     pub fn new(app_data_dir: PathBuf) -> Self {
         let path = app_data_dir.join("usage_cache.json");
-        let cache = Self::load_from_file(&path).unwrap_or_default();
-        
-        Self { path, cache }
-    }
-    
-    fn load_from_file(path: &PathBuf) -> Option<UsageCache> {
-        fs::read_to_string(path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
+
+        let mut keep_file = false;
+        let loaded = atomic::load_with(&path, "usage cache", |contents| {
+            parse_cache(contents).map_err(|e| match e {
+                CacheError::Serde(_) => atomic::Rejected::Corrupt(e),
+                _ => atomic::Rejected::Keep(e),
+            })
+        });
+        let (cache, migrated_from) = match loaded {
+            atomic::Loaded::Found((cache, version)) => (cache, Some(version).filter(|v| *v < CACHE_SCHEMA_VERSION)),
+            atomic::Loaded::Kept => {
+                keep_file = true;
+                (UsageCache::default(), None)
+            }
+            atomic::Loaded::Missing | atomic::Loaded::Quarantined => (UsageCache::default(), None),
+        };
+
+        let manager = Self { path, cache, stale_since: None, keep_file };
+
+        // Rewrite migrated files right away so older layouts don't linger on disk
+        if let Some(version) = migrated_from {
+            log::info!("Migrated usage cache from schema v{} to v{}", version, CACHE_SCHEMA_VERSION);
+            if let Err(e) = manager.save() {
+                log::warn!("Failed to persist migrated usage cache: {}", e);
+            }
+        }
+
+        manager
     }
-    
+
     pub fn save(&self) -> std::io::Result<()> {
-        if let Some(parent) = self.path.parent() {
-            fs::create_dir_all(parent)?;
+        if self.keep_file {
+            log::debug!("Not saving usage cache over a file that couldn't be loaded");
+            return Ok(());
         }
-        let json = serde_json::to_string_pretty(&self.cache)?;
-        fs::write(&self.path, json)
+        let envelope = CacheEnvelope {
+            version: CACHE_SCHEMA_VERSION,
+            saved_at: Utc::now(),
+            cache: self.cache.clone(),
+        };
+        let json = serde_json::to_string_pretty(&envelope)?;
+        atomic::write_atomic(&self.path, json)
     }
     
     pub fn get(&self, provider: &str) -> Option<&UsageData> {
@@ -89,6 +186,18 @@ impl CacheManager {
         &self.cache.providers
     }
     
+    /// Flag every current entry as out of date until it is refreshed
+    pub fn mark_stale(&mut self) {
+        self.stale_since = Some(Utc::now());
+    }
+    
+    pub fn is_stale(&self, provider: &str) -> bool {
+        match (self.stale_since, self.get(provider)) {
+            (Some(since), Some(data)) => data.last_updated < since,
+            _ => false,
+        }
+    }
+    
     pub fn clear_provider(&mut self, provider: &str) {
         self.cache.providers.remove(provider);
     }
@@ -96,4 +205,75 @@ impl CacheManager {
     pub fn clear_all(&mut self) {
         self.cache.providers.clear();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("limitswatcher-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_legacy_cache_is_migrated() {
+        let legacy = r#"{"providers":{"copilot":{"session_used":5,"session_limit":10,"weekly_used":0,"weekly_limit":0,"credits_remaining":null,"reset_time":null,"weekly_reset_time":null,"last_updated":"2025-01-01T00:00:00Z","error":null}}}"#;
+        let (cache, version) = parse_cache(legacy).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(cache.providers["copilot"].session_used, 5);
+    }
+
+    #[test]
+    fn test_future_version_is_rejected() {
+        let future = r#"{"version":99,"saved_at":"2025-01-01T00:00:00Z","cache":{"providers":{}}}"#;
+        assert!(matches!(parse_cache(future), Err(CacheError::UnsupportedVersion(99))));
+    }
+
+    #[test]
+    fn test_save_and_reload_roundtrip() {
+        let dir = temp_dir("roundtrip");
+        let mut manager = CacheManager::new(dir.clone());
+        manager.set("gemini", UsageData { session_used: 42, ..Default::default() });
+        manager.save().unwrap();
+
+        let reloaded = CacheManager::new(dir.clone());
+        assert_eq!(reloaded.get("gemini").unwrap().session_used, 42);
+        assert!(!dir.join("usage_cache.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_future_cache_is_kept() {
+        let dir = temp_dir("future");
+        let future = r#"{"version":99,"saved_at":"2025-01-01T00:00:00Z","cache":{"providers":{}}}"#;
+        fs::write(dir.join("usage_cache.json"), future).unwrap();
+
+        let mut manager = CacheManager::new(dir.clone());
+        manager.set("gemini", UsageData::default());
+        manager.save().unwrap();
+
+        assert_eq!(fs::read_to_string(dir.join("usage_cache.json")).unwrap(), future);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_cache_is_quarantined() {
+        let dir = temp_dir("corrupt");
+        fs::write(dir.join("usage_cache.json"), "{ not json").unwrap();
+
+        let manager = CacheManager::new(dir.clone());
+        assert!(manager.get_all().is_empty());
+
+        let quarantined = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .any(|e| e.file_name().to_string_lossy().starts_with("usage_cache.json.corrupt-"));
+        assert!(quarantined);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
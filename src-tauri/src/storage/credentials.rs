@@ -0,0 +1,491 @@
+//! Pluggable credential-store backends
+//!
+//! - `os-keyring`: OS keychain via `storage::keyring` (preferred)
+//! - `encrypted-file`: machine-bound AES-GCM file via `storage::encrypted`
+//! - `memory`: process-local, lost on exit (last resort and tests)
+//!
+//! The active backend is picked at startup by probing in that order. When the
+//! selection differs from the previous run, stored entries are migrated.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use super::atomic;
+use super::encrypted::{self, EncryptedStorageError, KdfParams, KeyMaterial};
+use super::keyring::{self, KeyringError, Result};
+
+const CREDENTIALS_FILE_NAME: &str = "credentials.enc";
+const BACKEND_STATE_FILE_NAME: &str = "credential_backend.json";
+const PROBE_KEY: &str = "__limitswatcher_probe";
+
+/// Storage backend for secrets keyed by name
+pub trait CredentialStore: Send + Sync {
+    fn kind(&self) -> BackendKind;
+
+    fn get(&self, key: &str) -> Result<Option<String>>;
+
+    fn set(&self, key: &str, value: &str) -> Result<()>;
+
+    fn delete(&self, key: &str) -> Result<()>;
+
+    /// Keys currently stored. Backends that cannot enumerate report the well-known keys they hold.
+    fn keys(&self) -> Result<Vec<String>>;
+
+    /// Check the backend answers a lookup. Read-only, so probing at every
+    /// startup never writes to (or prompts for) the user's keychain.
+    fn probe(&self) -> Result<()> {
+        self.get(PROBE_KEY).map(|_| ())
+    }
+
+    fn is_available(&self) -> bool {
+        self.probe().is_ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendKind {
+    OsKeyring,
+    EncryptedFile,
+    Memory,
+}
+
+impl BackendKind {
+    /// Automatic selection order
+    const FALLBACK_ORDER: [BackendKind; 3] = [BackendKind::OsKeyring, BackendKind::EncryptedFile, BackendKind::Memory];
+}
+
+/// Process-local store, used when nothing persistent works and in tests
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CredentialStore for MemoryStore {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Memory
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.entries.lock().unwrap_or_else(|e| e.into_inner()).get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        Ok(self.entries.lock().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect())
+    }
+}
+
+/// All entries sealed in a single machine-bound encrypted file
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    // Loaded lazily so an unreadable file only fails the calls that need it
+    entries: Mutex<Option<HashMap<String, String>>>,
+}
+
+impl EncryptedFileStore {
+    pub fn new(app_data_dir: &Path) -> Self {
+        Self {
+            path: app_data_dir.join(CREDENTIALS_FILE_NAME),
+            entries: Mutex::new(None),
+        }
+    }
+
+    fn with_entries<T>(&self, f: impl FnOnce(&mut HashMap<String, String>) -> T) -> Result<T> {
+        let mut guard = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            let loaded = if encrypted::exists(&self.path) {
+                let entries = encrypted::decrypt_from_file(&self.path, None)?;
+                self.upgrade(&entries);
+                entries
+            } else {
+                HashMap::new()
+            };
+            *guard = Some(loaded);
+        }
+        Ok(f(guard.as_mut().expect("entries loaded above")))
+    }
+
+    fn save(&self, entries: &HashMap<String, String>) -> Result<()> {
+        encrypted::encrypt_to_file(&self.path, entries, None)?;
+        Ok(())
+    }
+
+    /// Rewrite a file from an older format or with outdated KDF parameters
+    fn upgrade(&self, entries: &HashMap<String, String>) {
+        match encrypted::needs_upgrade(&self.path, &KdfParams::default()) {
+            Ok(false) => {}
+            Ok(true) => match self.save(entries) {
+                Ok(()) => log::info!("Upgraded encrypted credentials file to the current format"),
+                Err(e) => log::warn!("Failed to upgrade encrypted credentials file: {}", e),
+            },
+            Err(e) => log::warn!("Failed to read encrypted credentials header: {}", e),
+        }
+    }
+}
+
+impl CredentialStore for EncryptedFileStore {
+    fn kind(&self) -> BackendKind {
+        BackendKind::EncryptedFile
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        self.with_entries(|entries| entries.get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        let snapshot = self.with_entries(|entries| {
+            entries.insert(key.to_string(), value.to_string());
+            entries.clone()
+        })?;
+        self.save(&snapshot)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let snapshot = self.with_entries(|entries| entries.remove(key).map(|_| entries.clone()))?;
+        match snapshot {
+            Some(entries) => self.save(&entries),
+            None => Ok(()),
+        }
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        self.with_entries(|entries| entries.keys().cloned().collect())
+    }
+}
+
+/// Persisted record of which backend holds the credentials
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BackendState {
+    #[serde(default)]
+    backend: Option<BackendKind>,
+    /// User override; `None` means automatic selection
+    #[serde(default)]
+    preferred: Option<BackendKind>,
+}
+
+/// Active backend details reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendInfo {
+    pub backend: BackendKind,
+    pub preferred: Option<BackendKind>,
+    pub persistent: bool,
+    /// The encrypted credentials file was sealed under another machine identity
+    /// (e.g. after a hostname change); see [`recover_encrypted_file`]
+    pub needs_recovery: bool,
+}
+
+/// Key an encrypted credentials file was sealed with, for [`recover_encrypted_file`]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum RecoveryKey {
+    /// The previous `username@hostname`
+    PreviousMachine(String),
+    Passphrase(String),
+}
+
+fn open_backend(kind: BackendKind, app_data_dir: &Path) -> Arc<dyn CredentialStore> {
+    match kind {
+        BackendKind::OsKeyring => Arc::new(keyring::KeyringStore),
+        BackendKind::EncryptedFile => Arc::new(EncryptedFileStore::new(app_data_dir)),
+        BackendKind::Memory => Arc::new(MemoryStore::new()),
+    }
+}
+
+/// Copy every entry from one backend to another, then remove the originals.
+///
+/// Originals are only deleted once all copies succeeded. Returns the number of entries moved.
+pub fn migrate(from: &dyn CredentialStore, to: &dyn CredentialStore) -> Result<usize> {
+    let keys = from.keys()?;
+    let mut moved = Vec::new();
+    for key in keys {
+        if let Some(value) = from.get(&key)? {
+            to.set(&key, &value)?;
+            moved.push(key);
+        }
+    }
+    for key in &moved {
+        if let Err(e) = from.delete(key) {
+            log::warn!("Migrated credential '{}' but failed to remove the old copy: {}", key, e);
+        }
+    }
+    Ok(moved.len())
+}
+
+struct ActiveStore {
+    app_data_dir: PathBuf,
+    store: Arc<dyn CredentialStore>,
+    preferred: Option<BackendKind>,
+    needs_recovery: bool,
+}
+
+fn is_machine_key_mismatch(e: &KeyringError) -> bool {
+    matches!(e, KeyringError::Encrypted(EncryptedStorageError::MachineKeyMismatch))
+}
+
+static ACTIVE: OnceLock<RwLock<ActiveStore>> = OnceLock::new();
+
+fn state_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(BACKEND_STATE_FILE_NAME)
+}
+
+fn load_state(app_data_dir: &Path) -> BackendState {
+    std::fs::read_to_string(state_path(app_data_dir))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(app_data_dir: &Path, state: &BackendState) {
+    let result = serde_json::to_string_pretty(state)
+        .map_err(std::io::Error::from)
+        .and_then(|json| atomic::write_atomic(&state_path(app_data_dir), json));
+    if let Err(e) = result {
+        log::warn!("Failed to save credential backend state: {}", e);
+    }
+}
+
+/// Pick the first working backend, honoring a user preference when it works.
+/// Also reports whether the encrypted file was skipped for a machine key mismatch.
+fn select(app_data_dir: &Path, preferred: Option<BackendKind>) -> (Arc<dyn CredentialStore>, bool) {
+    let candidates = preferred
+        .into_iter()
+        .chain(BackendKind::FALLBACK_ORDER.into_iter().filter(|k| Some(*k) != preferred));
+
+    let mut mismatch = false;
+    for kind in candidates {
+        let store = open_backend(kind, app_data_dir);
+        match store.probe() {
+            Ok(()) => return (store, mismatch),
+            Err(e) if is_machine_key_mismatch(&e) => {
+                log::error!("Credential backend {:?} was sealed under another machine identity", kind);
+                mismatch = true;
+            }
+            Err(_) => log::warn!("Credential backend {:?} is unavailable, trying next", kind),
+        }
+    }
+    (Arc::new(MemoryStore::new()), mismatch)
+}
+
+/// Move entries left in the previously used backend into the current one.
+/// Returns whether they couldn't be read for a machine key mismatch.
+fn migrate_from_previous(app_data_dir: &Path, previous: Option<BackendKind>, current: &dyn CredentialStore) -> bool {
+    let previous = match previous {
+        Some(kind) if kind != current.kind() && kind != BackendKind::Memory => kind,
+        _ => return false,
+    };
+
+    let old = open_backend(previous, app_data_dir);
+    match migrate(old.as_ref(), current) {
+        Ok(count) => {
+            log::info!("Migrated {} credential(s) from {:?} to {:?}", count, previous, current.kind());
+            false
+        }
+        Err(e) => {
+            log::warn!("Could not migrate credentials from {:?}: {}", previous, e);
+            is_machine_key_mismatch(&e)
+        }
+    }
+}
+
+/// Select the credential backend and migrate entries if it changed. Call once during setup.
+pub fn init(app_data_dir: &Path) -> BackendKind {
+    let state = load_state(app_data_dir);
+    let (store, mut needs_recovery) = select(app_data_dir, state.preferred);
+    let kind = store.kind();
+
+    if kind == BackendKind::Memory {
+        // Keep the recorded backend so its entries are picked up once it works again
+        log::warn!("No persistent credential backend available; credentials will not survive a restart");
+    } else {
+        needs_recovery |= migrate_from_previous(app_data_dir, state.backend, store.as_ref());
+        save_state(app_data_dir, &BackendState { backend: Some(kind), preferred: state.preferred });
+    }
+
+    let active = ActiveStore {
+        app_data_dir: app_data_dir.to_path_buf(),
+        store,
+        preferred: state.preferred,
+        needs_recovery,
+    };
+    if ACTIVE.set(RwLock::new(active)).is_err() {
+        log::warn!("Credential store already initialized");
+    }
+    kind
+}
+
+/// The active backend; falls back to the OS keyring before `init` (e.g. in tests of other modules)
+pub fn active() -> Arc<dyn CredentialStore> {
+    match ACTIVE.get() {
+        Some(active) => active.read().unwrap_or_else(|e| e.into_inner()).store.clone(),
+        None => Arc::new(keyring::KeyringStore),
+    }
+}
+
+pub fn info() -> Option<BackendInfo> {
+    let active = ACTIVE.get()?.read().unwrap_or_else(|e| e.into_inner());
+    Some(BackendInfo {
+        backend: active.store.kind(),
+        preferred: active.preferred,
+        persistent: active.store.kind() != BackendKind::Memory,
+        needs_recovery: active.needs_recovery,
+    })
+}
+
+/// Switch to a specific backend (or back to automatic with `None`), migrating entries.
+/// Probing and migration are blocking I/O, so they run on a blocking thread.
+pub async fn set_preferred(preferred: Option<BackendKind>) -> Result<BackendKind> {
+    tauri::async_runtime::spawn_blocking(move || switch_backend(preferred))
+        .await
+        .map_err(|e| KeyringError::Unavailable(e.to_string()))?
+}
+
+fn switch_backend(preferred: Option<BackendKind>) -> Result<BackendKind> {
+    // One switch at a time; the active lock itself is only held for the swap
+    static SWITCHING: Mutex<()> = Mutex::new(());
+    let _switching = SWITCHING.lock().unwrap_or_else(|e| e.into_inner());
+
+    let lock = ACTIVE
+        .get()
+        .ok_or_else(|| KeyringError::Unavailable("credential store not initialized".into()))?;
+    let (app_data_dir, current) = {
+        let active = lock.read().unwrap_or_else(|e| e.into_inner());
+        (active.app_data_dir.clone(), active.store.clone())
+    };
+
+    let (next, _) = select(&app_data_dir, preferred);
+    if let Some(kind) = preferred {
+        if next.kind() != kind {
+            return Err(KeyringError::Unavailable(format!("{:?} backend is not available", kind)));
+        }
+    }
+    let switching = next.kind() != current.kind();
+    if switching {
+        migrate(current.as_ref(), next.as_ref())?;
+    }
+
+    let kind = {
+        let mut active = lock.write().unwrap_or_else(|e| e.into_inner());
+        if switching {
+            active.store = next.clone();
+        }
+        active.preferred = preferred;
+        active.store.kind()
+    };
+    save_state(&app_data_dir, &BackendState { backend: Some(kind), preferred });
+
+    // Pick up anything written to the old backend while the first pass ran
+    if switching {
+        if let Err(e) = migrate(current.as_ref(), next.as_ref()) {
+            log::warn!("Could not migrate credentials written during the switch: {}", e);
+        }
+    }
+    Ok(kind)
+}
+
+/// Re-seal the encrypted credentials file, written under a previous machine
+/// identity or a passphrase, with this machine's key and pick its entries up again
+pub async fn recover_encrypted_file(from: RecoveryKey) -> Result<BackendKind> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let lock = ACTIVE
+            .get()
+            .ok_or_else(|| KeyringError::Unavailable("credential store not initialized".into()))?;
+        let (app_data_dir, preferred) = {
+            let active = lock.read().unwrap_or_else(|e| e.into_inner());
+            (active.app_data_dir.clone(), active.preferred)
+        };
+
+        let key = match &from {
+            RecoveryKey::PreviousMachine(id) => KeyMaterial::PreviousMachine(id),
+            RecoveryKey::Passphrase(passphrase) => KeyMaterial::Passphrase(passphrase),
+        };
+        let path = app_data_dir.join(CREDENTIALS_FILE_NAME);
+        encrypted::reencrypt_file(&path, key, KeyMaterial::Machine, Some(KdfParams::default()))?;
+        lock.write().unwrap_or_else(|e| e.into_inner()).needs_recovery = false;
+
+        // Reselect in case the file was skipped, then move over what another backend got instead
+        let kind = switch_backend(preferred)?;
+        if kind != BackendKind::EncryptedFile {
+            let count = migrate(&EncryptedFileStore::new(&app_data_dir), active().as_ref())?;
+            log::info!("Recovered {} credential(s) from the encrypted file", count);
+        }
+        Ok(kind)
+    })
+    .await
+    .map_err(|e| KeyringError::Unavailable(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_moves_entries() {
+        let from = MemoryStore::new();
+        let to = MemoryStore::new();
+        from.set("copilot_access_token", "gho_abc").unwrap();
+        from.set("claude_cookies", "session=1").unwrap();
+
+        assert_eq!(migrate(&from, &to).unwrap(), 2);
+        assert_eq!(to.get("copilot_access_token").unwrap().as_deref(), Some("gho_abc"));
+        assert!(from.keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encrypted_file_store_persists() {
+        let dir = std::env::temp_dir().join(format!("limitswatcher-creds-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let store = EncryptedFileStore::new(&dir);
+        assert!(store.is_available());
+        assert!(!encrypted::exists(&dir.join(CREDENTIALS_FILE_NAME)));
+        store.set("gemini_oauth_token", "ya29").unwrap();
+
+        let reopened = EncryptedFileStore::new(&dir);
+        assert_eq!(reopened.get("gemini_oauth_token").unwrap().as_deref(), Some("ya29"));
+        assert_eq!(reopened.keys().unwrap(), vec!["gemini_oauth_token".to_string()]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_encrypted_file_recovers_from_machine_change() {
+        let dir = std::env::temp_dir().join(format!("limitswatcher-creds-recover-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CREDENTIALS_FILE_NAME);
+        let params = KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+
+        let entries = HashMap::from([("gemini_oauth_token".to_string(), "ya29".to_string())]);
+        let old = KeyMaterial::PreviousMachine("someone@old-host");
+        encrypted::encrypt_to_file_with(&path, &entries, old, params).unwrap();
+
+        let store = EncryptedFileStore::new(&dir);
+        assert!(is_machine_key_mismatch(&store.probe().unwrap_err()));
+
+        encrypted::reencrypt_file(&path, old, KeyMaterial::Machine, Some(params)).unwrap();
+        assert_eq!(store.get("gemini_oauth_token").unwrap().as_deref(), Some("ya29"));
+        // Outdated parameters are rewritten on load
+        assert!(!encrypted::needs_upgrade(&path, &KdfParams::default()).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,18 +1,35 @@
 //! AES-256-GCM encrypted file storage for cookies and sensitive bulk data
+//!
+//! Every blob carries a versioned header recording the cipher, the Argon2id
+//! parameters and where the key came from, so parameters can be raised later
+//! and blobs can be re-keyed without guessing how they were written.
 
 use aes_gcm::{
     aead::{Aead, KeyInit, OsRng},
     Aes256Gcm, Nonce,
 };
 use aes_gcm::aead::rand_core::RngCore;
-use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use argon2::{Algorithm, Argon2, Params, Version};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
+use zeroize::Zeroize;
+
+use super::atomic;
 
 const NONCE_SIZE: usize = 12;
 const SALT_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+
+/// Current blob format version
+///
+/// - v1: `{ salt, nonce, ciphertext }`, implicit AES-256-GCM + default Argon2id
+/// - v2: adds `version`, `algorithm`, `kdf` and `key_source` header fields
+pub const BLOB_FORMAT_VERSION: u32 = 2;
+
+const ALGORITHM_AES_256_GCM: &str = "aes-256-gcm";
+const KDF_ARGON2ID: &str = "argon2id";
 
 #[derive(Debug, thiserror::Error)]
 pub enum EncryptedStorageError {
@@ -20,6 +37,10 @@ pub enum EncryptedStorageError {
     Encryption,
     #[error("Decryption error")]
     Decryption,
+    #[error("Decryption failed: machine identity may have changed (blob was bound to this machine's user and hostname)")]
+    MachineKeyMismatch,
+    #[error("Unsupported blob format: {0}")]
+    UnsupportedFormat(String),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     #[error("Serialization error: {0}")]
@@ -32,124 +53,430 @@ pub enum EncryptedStorageError {
 
 pub type Result<T> = std::result::Result<T, EncryptedStorageError>;
 
-#[derive(Serialize, Deserialize)]
-struct EncryptedBlob {
-    salt: String,      // Base64 encoded
-    nonce: String,     // Base64 encoded
+/// Argon2id cost parameters recorded alongside each blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub m_cost: u32,
+    /// Number of iterations
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct KdfHeader {
+    algorithm: String,
+    #[serde(flatten)]
+    params: KdfParams,
+}
+
+impl Default for KdfHeader {
+    fn default() -> Self {
+        Self {
+            algorithm: KDF_ARGON2ID.to_string(),
+            params: KdfParams::default(),
+        }
+    }
+}
+
+/// What the blob key was derived from (recorded in the header, never the secret itself)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Bound to `username@hostname`
+    Machine,
+    /// User-supplied passphrase
+    Passphrase,
+    /// Written by a v1 build, which did not record the source
+    Unknown,
+}
+
+/// Secret input used to derive a blob key
+#[derive(Debug, Clone, Copy)]
+pub enum KeyMaterial<'a> {
+    /// The current machine identity
+    Machine,
+    /// A previous machine identity (e.g. `alice@old-hostname`) for recovery after a rename
+    PreviousMachine(&'a str),
+    /// A user passphrase
+    Passphrase(&'a str),
+    /// A passphrase key derived earlier and kept in memory (see `DerivedKey`)
+    Derived(&'a DerivedKey),
+}
+
+impl<'a> KeyMaterial<'a> {
+    /// Map the optional password accepted by the file helpers
+    pub fn from_password(password: Option<&'a str>) -> Self {
+        match password {
+            Some(p) => KeyMaterial::Passphrase(p),
+            None => KeyMaterial::Machine,
+        }
+    }
+
+    /// Resolve the AES key for a blob with the given salt and parameters
+    fn key_for(&self, salt: &[u8], params: &KdfParams) -> Result<[u8; KEY_SIZE]> {
+        match self {
+            KeyMaterial::Machine => derive_key(&current_machine_id(), salt, params),
+            KeyMaterial::PreviousMachine(id) => derive_key(id, salt, params),
+            KeyMaterial::Passphrase(p) => derive_key(p, salt, params),
+            KeyMaterial::Derived(key) => {
+                // A cached key is only valid for blobs sharing its salt and parameters
+                if key.salt == salt && key.params == *params {
+                    Ok(key.key)
+                } else {
+                    Err(EncryptedStorageError::Decryption)
+                }
+            }
+        }
+    }
+
+    fn source(&self) -> KeySource {
+        match self {
+            KeyMaterial::Machine | KeyMaterial::PreviousMachine(_) => KeySource::Machine,
+            KeyMaterial::Passphrase(_) | KeyMaterial::Derived(_) => KeySource::Passphrase,
+        }
+    }
+}
+
+/// A passphrase-derived key held in memory so it is not re-derived on every access.
+///
+/// Blobs sealed with it reuse its salt and parameters. The key bytes are wiped on drop.
+pub struct DerivedKey {
+    key: [u8; KEY_SIZE],
+    salt: Vec<u8>,
+    params: KdfParams,
+}
+
+impl DerivedKey {
+    /// Derive a fresh key with a random salt
+    pub fn generate(passphrase: &str, params: KdfParams) -> Result<Self> {
+        let mut salt = vec![0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(passphrase, &salt, &params)?;
+        Ok(Self { key, salt, params })
+    }
+
+    /// Re-derive the key that sealed an existing blob
+    pub fn for_blob(blob: &EncryptedBlob, passphrase: &str) -> Result<Self> {
+        let salt = BASE64.decode(&blob.salt)?;
+        let params = blob.kdf.params;
+        let key = derive_key(passphrase, &salt, &params)?;
+        Ok(Self { key, salt, params })
+    }
+}
+
+impl Drop for DerivedKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl std::fmt::Debug for DerivedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DerivedKey").field("params", &self.params).finish_non_exhaustive()
+    }
+}
+
+fn legacy_version() -> u32 {
+    1
+}
+
+fn default_algorithm() -> String {
+    ALGORITHM_AES_256_GCM.to_string()
+}
+
+fn unknown_key_source() -> KeySource {
+    KeySource::Unknown
+}
+
+/// Serialized blob; header fields default to v1 semantics when absent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedBlob {
+    #[serde(default = "legacy_version")]
+    version: u32,
+    #[serde(default = "default_algorithm")]
+    algorithm: String,
+    #[serde(default)]
+    kdf: KdfHeader,
+    #[serde(default = "unknown_key_source")]
+    key_source: KeySource,
+    salt: String,       // Base64 encoded
+    nonce: String,      // Base64 encoded
     ciphertext: String, // Base64 encoded
 }
 
-/// Derive a 256-bit key from a password using Argon2id
-fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
-    let salt_string = SaltString::encode_b64(salt)
+/// Public view of a blob header, for diagnostics and deciding whether to re-key
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobHeader {
+    pub version: u32,
+    pub algorithm: String,
+    pub kdf_algorithm: String,
+    pub kdf_params: KdfParams,
+    pub key_source: KeySource,
+}
+
+impl EncryptedBlob {
+    pub fn header(&self) -> BlobHeader {
+        BlobHeader {
+            version: self.version,
+            algorithm: self.algorithm.clone(),
+            kdf_algorithm: self.kdf.algorithm.clone(),
+            kdf_params: self.kdf.params,
+            key_source: self.key_source,
+        }
+    }
+}
+
+/// Derive a 256-bit key from a secret using Argon2id with explicit parameters
+fn derive_key(secret: &str, salt: &[u8], params: &KdfParams) -> Result<[u8; KEY_SIZE]> {
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(KEY_SIZE))
         .map_err(|_| EncryptedStorageError::KeyDerivation)?;
-    
-    let argon2 = Argon2::default();
-    let hash = argon2
-        .hash_password(password.as_bytes(), &salt_string)
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key = [0u8; KEY_SIZE];
+    argon2
+        .hash_password_into(secret.as_bytes(), salt, &mut key)
         .map_err(|_| EncryptedStorageError::KeyDerivation)?;
-    
-    let hash_bytes = hash.hash.ok_or(EncryptedStorageError::KeyDerivation)?;
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&hash_bytes.as_bytes()[..32]);
     Ok(key)
 }
 
-/// Get machine-specific identifier for key derivation
-fn get_machine_id() -> String {
+/// Machine-specific identifier used for key derivation (`username@hostname`)
+pub fn current_machine_id() -> String {
     // Use combination of factors for machine binding
     let machine_hostname: String = match hostname::get() {
         Ok(h) => h.to_string_lossy().to_string(),
         Err(_) => "unknown".to_string(),
     };
-    
+
     let username: String = whoami::username().unwrap_or_else(|_| "unknown".to_string());
-    
+
     format!("{}@{}", username, machine_hostname)
 }
 
-/// Encrypt data and save to file
-pub fn encrypt_to_file<T: Serialize>(path: &PathBuf, data: &T, password: Option<&str>) -> Result<()> {
+/// Encrypt a serializable value into an in-memory blob
+///
+/// `params` is ignored for `KeyMaterial::Derived`, which carries its own.
+pub fn encrypt_blob<T: Serialize>(data: &T, key: KeyMaterial, params: KdfParams) -> Result<EncryptedBlob> {
     let json = serde_json::to_string(data)?;
-    
-    // Use password or machine ID for key derivation
-    let key_source = password
-        .map(|p| p.to_string())
-        .unwrap_or_else(get_machine_id);
-    
-    // Generate random salt and nonce
-    let mut salt = [0u8; SALT_SIZE];
+
+    // Generate random salt (unless reusing a cached key) and nonce
+    let (salt, params) = match key {
+        KeyMaterial::Derived(derived) => (derived.salt.clone(), derived.params),
+        _ => {
+            let mut salt = vec![0u8; SALT_SIZE];
+            OsRng.fill_bytes(&mut salt);
+            (salt, params)
+        }
+    };
     let mut nonce_bytes = [0u8; NONCE_SIZE];
-    OsRng.fill_bytes(&mut salt);
     OsRng.fill_bytes(&mut nonce_bytes);
-    
+
     // Derive key and encrypt
-    let key = derive_key(&key_source, &salt)?;
-    let cipher = Aes256Gcm::new_from_slice(&key)
+    let mut derived = key.key_for(&salt, &params)?;
+    let cipher = Aes256Gcm::new_from_slice(&derived)
         .map_err(|_| EncryptedStorageError::Encryption)?;
     let nonce = Nonce::from_slice(&nonce_bytes);
-    
+
     let ciphertext = cipher
         .encrypt(nonce, json.as_bytes())
         .map_err(|_| EncryptedStorageError::Encryption)?;
-    
-    // Create blob and save
-    let blob = EncryptedBlob {
+    derived.zeroize();
+
+    Ok(EncryptedBlob {
+        version: BLOB_FORMAT_VERSION,
+        algorithm: ALGORITHM_AES_256_GCM.to_string(),
+        kdf: KdfHeader {
+            algorithm: KDF_ARGON2ID.to_string(),
+            params,
+        },
+        key_source: key.source(),
         salt: BASE64.encode(salt),
         nonce: BASE64.encode(nonce_bytes),
         ciphertext: BASE64.encode(ciphertext),
-    };
-    
-    let blob_json = serde_json::to_string_pretty(&blob)?;
-    
-    // Ensure parent directory exists
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    
-    fs::write(path, blob_json)?;
-    Ok(())
+    })
 }
 
-/// Decrypt data from file
-pub fn decrypt_from_file<T: for<'de> Deserialize<'de>>(path: &PathBuf, password: Option<&str>) -> Result<T> {
-    let blob_json = fs::read_to_string(path)?;
-    let blob: EncryptedBlob = serde_json::from_str(&blob_json)?;
-    
+/// Decrypt an in-memory blob
+pub fn decrypt_blob<T: DeserializeOwned>(blob: &EncryptedBlob, key: KeyMaterial) -> Result<T> {
+    if blob.version > BLOB_FORMAT_VERSION {
+        return Err(EncryptedStorageError::UnsupportedFormat(format!("version {}", blob.version)));
+    }
+    if blob.algorithm != ALGORITHM_AES_256_GCM {
+        return Err(EncryptedStorageError::UnsupportedFormat(blob.algorithm.clone()));
+    }
+    if blob.kdf.algorithm != KDF_ARGON2ID {
+        return Err(EncryptedStorageError::UnsupportedFormat(blob.kdf.algorithm.clone()));
+    }
+
     let salt = BASE64.decode(&blob.salt)?;
     let nonce_bytes = BASE64.decode(&blob.nonce)?;
     let ciphertext = BASE64.decode(&blob.ciphertext)?;
-    
-    // Use password or machine ID for key derivation
-    let key_source = password
-        .map(|p| p.to_string())
-        .unwrap_or_else(get_machine_id);
-    
+    if nonce_bytes.len() != NONCE_SIZE {
+        return Err(EncryptedStorageError::UnsupportedFormat("nonce length".into()));
+    }
+
     // Derive key and decrypt
-    let key = derive_key(&key_source, &salt)?;
-    let cipher = Aes256Gcm::new_from_slice(&key)
+    let mut derived = key.key_for(&salt, &blob.kdf.params)?;
+    let cipher = Aes256Gcm::new_from_slice(&derived)
         .map_err(|_| EncryptedStorageError::Decryption)?;
+    derived.zeroize();
     let nonce = Nonce::from_slice(&nonce_bytes);
-    
-    let plaintext = cipher
-        .decrypt(nonce, ciphertext.as_ref())
-        .map_err(|_| EncryptedStorageError::Decryption)?;
-    
-    let json = String::from_utf8(plaintext)
-        .map_err(|_| EncryptedStorageError::Decryption)?;
-    
-    Ok(serde_json::from_str(&json)?)
+
+    let plaintext = cipher.decrypt(nonce, ciphertext.as_ref()).map_err(|_| {
+        // A machine-bound blob that no longer opens most likely means a host or user rename
+        match (key, blob.key_source) {
+            (KeyMaterial::Machine, KeySource::Machine | KeySource::Unknown) => {
+                EncryptedStorageError::MachineKeyMismatch
+            }
+            _ => EncryptedStorageError::Decryption,
+        }
+    })?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Encrypt data and save to file
+pub fn encrypt_to_file<T: Serialize>(path: &Path, data: &T, password: Option<&str>) -> Result<()> {
+    encrypt_to_file_with(path, data, KeyMaterial::from_password(password), KdfParams::default())
+}
+
+/// Encrypt data with explicit key material and KDF parameters, then save to file atomically
+pub fn encrypt_to_file_with<T: Serialize>(
+    path: &Path,
+    data: &T,
+    key: KeyMaterial,
+    params: KdfParams,
+) -> Result<()> {
+    let blob = encrypt_blob(data, key, params)?;
+    let blob_json = serde_json::to_string_pretty(&blob)?;
+    atomic::write_atomic(path, blob_json)?;
+    Ok(())
+}
+
+/// Decrypt data from file
+pub fn decrypt_from_file<T: DeserializeOwned>(path: &Path, password: Option<&str>) -> Result<T> {
+    decrypt_from_file_with(path, KeyMaterial::from_password(password))
+}
+
+/// Decrypt data from file with explicit key material
+pub fn decrypt_from_file_with<T: DeserializeOwned>(path: &Path, key: KeyMaterial) -> Result<T> {
+    let blob = read_blob(path)?;
+    decrypt_blob(&blob, key)
+}
+
+/// Read a blob from disk without decrypting it
+pub fn read_blob(path: &Path) -> Result<EncryptedBlob> {
+    let blob_json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&blob_json)?)
+}
+
+/// Read only the header of an encrypted file
+pub fn read_header(path: &Path) -> Result<BlobHeader> {
+    Ok(read_blob(path)?.header())
+}
+
+/// Re-encrypt a file under new key material and/or KDF parameters.
+///
+/// Covers key rotation (new passphrase), parameter upgrades, legacy v1 upgrades,
+/// and recovery after a machine identity change, e.g.
+/// `reencrypt_file(path, KeyMaterial::PreviousMachine("alice@old-host"), KeyMaterial::Passphrase(p), ..)`.
+pub fn reencrypt_file(
+    path: &Path,
+    from: KeyMaterial,
+    to: KeyMaterial,
+    params: Option<KdfParams>,
+) -> Result<()> {
+    let blob = read_blob(path)?;
+    let params = params.unwrap_or(blob.kdf.params);
+    let value: serde_json::Value = decrypt_blob(&blob, from)?;
+    encrypt_to_file_with(path, &value, to, params)
+}
+
+/// Check whether a file should be re-encrypted to pick up the current format or parameters
+pub fn needs_upgrade(path: &Path, params: &KdfParams) -> Result<bool> {
+    let header = read_header(path)?;
+    Ok(header.version < BLOB_FORMAT_VERSION || header.kdf_params != *params)
 }
 
 /// Check if encrypted file exists
-pub fn exists(path: &PathBuf) -> bool {
+pub fn exists(path: &Path) -> bool {
     path.exists()
 }
 
 /// Delete encrypted file
-pub fn delete(path: &PathBuf) -> Result<()> {
+pub fn delete(path: &Path) -> Result<()> {
     if path.exists() {
         fs::remove_file(path)?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // Cheap parameters keep the tests fast
+    const TEST_PARAMS: KdfParams = KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+
+    #[test]
+    fn test_blob_roundtrip_records_header() {
+        let blob = encrypt_blob(&"secret", KeyMaterial::Passphrase("pw"), TEST_PARAMS).unwrap();
+        let header = blob.header();
+        assert_eq!(header.version, BLOB_FORMAT_VERSION);
+        assert_eq!(header.kdf_params, TEST_PARAMS);
+        assert_eq!(header.key_source, KeySource::Passphrase);
+
+        let value: String = decrypt_blob(&blob, KeyMaterial::Passphrase("pw")).unwrap();
+        assert_eq!(value, "secret");
+        assert!(decrypt_blob::<String>(&blob, KeyMaterial::Passphrase("wrong")).is_err());
+    }
+
+    #[test]
+    fn test_legacy_blob_still_decrypts() {
+        // Written by the v1 `encrypt_to_file` with passphrase "v1-fixture"
+        let fixture = include_str!("testdata/encrypted_v1.json");
+        let legacy: EncryptedBlob = serde_json::from_str(fixture).unwrap();
+        assert_eq!(legacy.header().version, 1);
+        assert_eq!(legacy.header().key_source, KeySource::Unknown);
+
+        let entries: HashMap<String, String> = decrypt_blob(&legacy, KeyMaterial::Passphrase("v1-fixture")).unwrap();
+        assert_eq!(entries["copilot_access_token"], "gho_v1fixture");
+
+        let path = std::env::temp_dir().join(format!("limitswatcher-v1-{}.enc", std::process::id()));
+        fs::write(&path, fixture).unwrap();
+        assert!(needs_upgrade(&path, &TEST_PARAMS).unwrap());
+
+        reencrypt_file(&path, KeyMaterial::Passphrase("v1-fixture"), KeyMaterial::Passphrase("v1-fixture"), Some(TEST_PARAMS))
+            .unwrap();
+        assert!(!needs_upgrade(&path, &TEST_PARAMS).unwrap());
+        let upgraded: HashMap<String, String> = decrypt_from_file(&path, Some("v1-fixture")).unwrap();
+        assert_eq!(upgraded, entries);
+        delete(&path).unwrap();
+    }
+
+    #[test]
+    fn test_recover_from_previous_machine_id() {
+        let path = std::env::temp_dir().join(format!("limitswatcher-blob-{}.enc", std::process::id()));
+        let old_id = "alice@old-host";
+        encrypt_to_file_with(&path, &"token", KeyMaterial::PreviousMachine(old_id), TEST_PARAMS).unwrap();
+
+        reencrypt_file(&path, KeyMaterial::PreviousMachine(old_id), KeyMaterial::Passphrase("pw"), None).unwrap();
+        assert_eq!(read_header(&path).unwrap().key_source, KeySource::Passphrase);
+
+        let value: String = decrypt_from_file(&path, Some("pw")).unwrap();
+        assert_eq!(value, "token");
+        delete(&path).unwrap();
+    }
+}
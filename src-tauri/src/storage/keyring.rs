@@ -2,9 +2,19 @@
 //! - Windows: Credential Manager
 //! - macOS: Keychain
 //! - Linux: Secret Service (GNOME Keyring / KWallet)
+//!
+//! The free functions below go through the active `CredentialStore` backend
+//! (see `storage::credentials`), of which the OS keychain is the preferred one.
+//! When vault mode is enabled, credentials are routed to the passphrase-locked
+//! vault instead (see `storage::vault`).
 
 use keyring::Entry;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::credentials::{self, BackendKind, CredentialStore};
+use super::encrypted::EncryptedStorageError;
+use super::vault::{self, VaultError};
 
 const SERVICE_NAME: &str = "com.limitswatcher";
 
@@ -14,35 +24,116 @@ pub enum KeyringError {
     Keyring(#[from] keyring::Error),
     #[error("Serialization error: {0}")]
     Serde(#[from] serde_json::Error),
+    #[error("Vault error: {0}")]
+    Vault(#[from] VaultError),
+    #[error("Encrypted storage error: {0}")]
+    Encrypted(#[from] EncryptedStorageError),
+    #[error("Credential store unavailable: {0}")]
+    Unavailable(String),
 }
 
 pub type Result<T> = std::result::Result<T, KeyringError>;
 
-/// Store a credential in the OS keychain
+/// OS keychain backend
+pub struct KeyringStore;
+
+impl CredentialStore for KeyringStore {
+    fn kind(&self) -> BackendKind {
+        BackendKind::OsKeyring
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        let entry = Entry::new(SERVICE_NAME, key)?;
+        match entry.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        let entry = Entry::new(SERVICE_NAME, key)?;
+        entry.set_password(value)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let entry = Entry::new(SERVICE_NAME, key)?;
+        match entry.delete_credential() {
+            Ok(()) => Ok(()),
+            Err(keyring::Error::NoEntry) => Ok(()), // Already deleted
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        // The keychain can't be enumerated portably, so probe the well-known keys
+        let mut found = Vec::new();
+        for key in keys::ALL {
+            if self.get(key)?.is_some() {
+                found.push(key.to_string());
+            }
+        }
+        // Per-sink keys are listed in an index entry
+        if let Some(index) = self.get(keys::NOTIFICATION_SINKS)? {
+            for key in serde_json::from_str::<Vec<String>>(&index).unwrap_or_default() {
+                if self.get(&key)?.is_some() {
+                    found.push(key);
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// The active backend, or the vault when enabled: the free functions below as a
+/// `CredentialStore`, for secrets that must follow provider credentials into the vault
+pub struct RoutedStore;
+
+impl CredentialStore for RoutedStore {
+    fn kind(&self) -> BackendKind {
+        credentials::active().kind()
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        get_credential(key)
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        store_credential(key, value)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        delete_credential(key)
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        Ok(export_credentials()?.into_keys().collect())
+    }
+}
+
+/// Store a credential in the active backend (or the vault when enabled)
 pub fn store_credential(key: &str, value: &str) -> Result<()> {
-    let entry = Entry::new(SERVICE_NAME, key)?;
-    entry.set_password(value)?;
-    Ok(())
+    if let Some(result) = vault::with_enabled_vault(|v| v.store(key, value)) {
+        return Ok(result?);
+    }
+    credentials::active().set(key, value)
 }
 
-/// Retrieve a credential from the OS keychain
+/// Retrieve a credential from the active backend (or the vault when enabled)
 pub fn get_credential(key: &str) -> Result<Option<String>> {
-    let entry = Entry::new(SERVICE_NAME, key)?;
-    match entry.get_password() {
-        Ok(password) => Ok(Some(password)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(e.into()),
+    if let Some(result) = vault::with_enabled_vault(|v| v.get(key)) {
+        return Ok(result?);
     }
+    credentials::active().get(key)
 }
 
-/// Delete a credential from the OS keychain
+/// Delete a credential from the active backend (or the vault when enabled)
 pub fn delete_credential(key: &str) -> Result<()> {
-    let entry = Entry::new(SERVICE_NAME, key)?;
-    match entry.delete_credential() {
-        Ok(()) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()), // Already deleted
-        Err(e) => Err(e.into()),
+    if let Some(result) = vault::with_enabled_vault(|v| v.delete(key)) {
+        return Ok(result?);
     }
+    credentials::active().delete(key)
 }
 
 /// Store a structured credential (serialized as JSON)
@@ -59,6 +150,62 @@ pub fn get_credential_json<T: for<'de> Deserialize<'de>>(key: &str) -> Result<Op
     }
 }
 
+/// Snapshot every stored credential (from the vault when enabled, which must be unlocked)
+pub fn export_credentials() -> Result<HashMap<String, String>> {
+    if let Some(result) = vault::with_enabled_vault(|v| v.entries()) {
+        return Ok(result?);
+    }
+    let store = credentials::active();
+    let mut entries = HashMap::new();
+    for key in store.keys()? {
+        if let Some(value) = store.get(&key)? {
+            entries.insert(key, value);
+        }
+    }
+    Ok(entries)
+}
+
+/// Store a set of credentials, e.g. from a restored backup
+pub fn import_credentials(entries: &HashMap<String, String>) -> Result<()> {
+    for (key, value) in entries {
+        store_credential(key, value)?;
+    }
+    Ok(())
+}
+
+/// Move all credentials from the active backend into a newly enabled vault
+pub async fn enable_vault(passphrase: &str) -> Result<()> {
+    let store = credentials::active();
+    let mut entries = HashMap::new();
+    for key in store.keys()? {
+        if let Some(value) = store.get(&key)? {
+            entries.insert(key, value);
+        }
+    }
+    let moved: Vec<String> = entries.keys().cloned().collect();
+
+    vault::enable(passphrase.to_string(), entries).await?;
+
+    // Only remove the backend copies once the vault is safely on disk
+    for key in moved {
+        store.delete(&key)?;
+    }
+    Ok(())
+}
+
+/// Move vault credentials back into the active backend and turn vault mode off
+pub async fn disable_vault(passphrase: &str) -> Result<()> {
+    vault::unlock(passphrase.to_string()).await?;
+    let entries = vault::with_vault(|v| v.entries())??;
+    let store = credentials::active();
+    for (key, value) in &entries {
+        store.set(key, value)?;
+    }
+    // Only delete the vault once every entry is safely in the backend
+    vault::with_vault(|v| v.disable())??;
+    Ok(())
+}
+
 // Provider-specific key helpers
 pub mod keys {
     pub const COPILOT_TOKEN: &str = "copilot_access_token";
@@ -66,23 +213,61 @@ pub mod keys {
     pub const CLAUDE_COOKIES: &str = "claude_cookies";
     pub const GEMINI_OAUTH: &str = "gemini_oauth_token";
     pub const ANTIGRAVITY_CONFIG: &str = "antigravity_config";
+    /// Index of the [`notification_sink`] keys in use
+    pub const NOTIFICATION_SINKS: &str = "notification_sinks";
+
+    /// Every well-known credential key
+    pub const ALL: [&str; 6] = [
+        COPILOT_TOKEN,
+        CLAUDE_OAUTH,
+        CLAUDE_COOKIES,
+        GEMINI_OAUTH,
+        ANTIGRAVITY_CONFIG,
+        NOTIFICATION_SINKS,
+    ];
+
+    /// A notification sink's target, with its URLs and tokens
+    pub fn notification_sink(id: &str) -> String {
+        format!("notification_sink.{}", id)
+    }
+
+    /// Keys holding notification sink secrets rather than provider credentials
+    pub fn is_notification_sink(key: &str) -> bool {
+        key == NOTIFICATION_SINKS || key.starts_with("notification_sink.")
+    }
+
+    /// Well-known provider credential keys. Other names can't be found again by
+    /// the OS keychain, which is only probed for known keys, so aren't accepted.
+    pub fn is_provider_credential(key: &str) -> bool {
+        ALL.contains(&key) && !is_notification_sink(key)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::credentials::MemoryStore;
 
     #[test]
     fn test_credential_roundtrip() {
+        // Exercise the backend contract without touching the real keychain
+        let store = MemoryStore::new();
         let key = "test_credential";
         let value = "test_value_12345";
         
-        store_credential(key, value).unwrap();
-        let retrieved = get_credential(key).unwrap();
+        store.set(key, value).unwrap();
+        let retrieved = store.get(key).unwrap();
         assert_eq!(retrieved, Some(value.to_string()));
         
-        delete_credential(key).unwrap();
-        let deleted = get_credential(key).unwrap();
+        store.delete(key).unwrap();
+        let deleted = store.get(key).unwrap();
         assert_eq!(deleted, None);
     }
+
+    #[test]
+    fn test_only_known_provider_keys_are_accepted() {
+        assert!(keys::is_provider_credential(keys::GEMINI_OAUTH));
+        assert!(!keys::is_provider_credential(keys::NOTIFICATION_SINKS));
+        assert!(!keys::is_provider_credential("gemini_api_key"));
+    }
 }
@@ -1,5 +1,9 @@
 pub mod keyring;
 pub mod encrypted;
 pub mod cache;
+pub mod atomic;
+pub mod vault;
+pub mod credentials;
+pub mod backup;
 
-pub use cache::{CacheManager, UsageCache, UsageData, ModelQuota};
\ No newline at end of file
+pub use cache::{CacheManager, UsageCache, UsageData, ModelQuota};
@@ -0,0 +1,484 @@
+//! Opt-in passphrase-locked vault for credentials
+//!
+//! When enabled, credentials are sealed with a key derived from the user's master
+//! passphrase instead of living in the OS keychain. The derived key is only held
+//! in memory while the vault is unlocked and is dropped on lock or idle timeout.
+//!
+//! Key derivation is deliberately slow, so the async entry points (`unlock`,
+//! `enable`, `change_passphrase`) derive on a blocking thread and take the vault
+//! lock only to install the result.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::atomic;
+use super::keyring::keys;
+use super::encrypted::{self, DerivedKey, EncryptedBlob, EncryptedStorageError, KdfParams, KeyMaterial};
+
+const VAULT_FILE_NAME: &str = "vault.json";
+const VAULT_FORMAT_VERSION: u32 = 1;
+
+/// Idle time before an unlocked vault locks itself, unless changed by the user
+pub const DEFAULT_AUTO_LOCK_MINUTES: u64 = 15;
+
+/// Master passphrase KDF cost (stronger than the per-file machine-bound default)
+const VAULT_KDF_PARAMS: KdfParams = KdfParams {
+    m_cost: 64 * 1024,
+    t_cost: 3,
+    p_cost: 1,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum VaultError {
+    #[error("Vault is not enabled")]
+    NotEnabled,
+    #[error("Vault is already enabled")]
+    AlreadyEnabled,
+    #[error("Vault is locked")]
+    Locked,
+    #[error("Incorrect passphrase")]
+    WrongPassphrase,
+    #[error("Passphrase must not be empty")]
+    EmptyPassphrase,
+    #[error("Vault file can't be read")]
+    Unreadable,
+    #[error("Vault not initialized")]
+    NotInitialized,
+    #[error("Vault changed while the key was derived; try again")]
+    Changed,
+    #[error("Key derivation failed: {0}")]
+    Task(String),
+    #[error("Encryption error: {0}")]
+    Encrypted(#[from] EncryptedStorageError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, VaultError>;
+
+/// On-disk vault layout. Entry names are stored in the clear so the scheduler
+/// can tell which providers are affected while the vault is locked.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    #[serde(default)]
+    auto_lock_minutes: Option<u64>,
+    #[serde(default)]
+    entry_keys: BTreeSet<String>,
+    data: EncryptedBlob,
+}
+
+/// Vault state reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub auto_lock_minutes: Option<u64>,
+    pub providers: Vec<String>,
+}
+
+struct Unlocked {
+    key: DerivedKey,
+    entries: HashMap<String, String>,
+}
+
+pub struct Vault {
+    path: PathBuf,
+    params: KdfParams,
+    file: Option<VaultFile>,
+    /// The vault file exists but couldn't be read; retried on unlock
+    unreadable: bool,
+    unlocked: Option<Unlocked>,
+    last_activity: Instant,
+}
+
+impl Vault {
+    pub fn load(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join(VAULT_FILE_NAME);
+        // A vault that exists but can't be read stays enabled and locked, so
+        // credentials never fall back to the plain store or overwrite it
+        let (file, unreadable) = match read_file(&path) {
+            Ok(file) => (file, false),
+            Err(_) => {
+                log::error!("Vault file can't be read, keeping the vault locked");
+                (None, true)
+            }
+        };
+
+        Self {
+            path,
+            params: VAULT_KDF_PARAMS,
+            file,
+            unreadable,
+            unlocked: None,
+            last_activity: Instant::now(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some() || self.unreadable
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.is_enabled() && self.unlocked.is_none()
+    }
+
+    pub fn status(&self) -> VaultStatus {
+        VaultStatus {
+            enabled: self.is_enabled(),
+            locked: self.is_locked(),
+            auto_lock_minutes: self.file.as_ref().and_then(|f| f.auto_lock_minutes),
+            providers: self.providers(),
+        }
+    }
+
+    /// Provider ids with at least one credential in the vault (keys are `<provider>_<kind>`)
+    pub fn providers(&self) -> Vec<String> {
+        let mut providers: Vec<String> = self
+            .file
+            .iter()
+            .flat_map(|f| f.entry_keys.iter())
+            .filter(|k| !keys::is_notification_sink(k))
+            .filter_map(|k| k.split('_').next().map(str::to_string))
+            .collect();
+        providers.dedup();
+        providers
+    }
+
+    /// Whether a provider's credentials live in the vault
+    pub fn holds_provider(&self, provider: &str) -> bool {
+        let prefix = format!("{}_", provider);
+        // Until an unreadable file can be read, assume it holds everything
+        self.unreadable
+            || self
+                .file
+                .as_ref()
+                .map(|f| f.entry_keys.iter().any(|k| k.starts_with(&prefix)))
+                .unwrap_or(false)
+    }
+
+    /// Turn on vault mode, sealing the given entries under `key`
+    fn enable_with_key(&mut self, key: DerivedKey, entries: HashMap<String, String>) -> Result<()> {
+        if self.is_enabled() {
+            return Err(VaultError::AlreadyEnabled);
+        }
+        self.unlocked = Some(Unlocked { key, entries });
+        self.persist(Some(DEFAULT_AUTO_LOCK_MINUTES))?;
+        self.touch();
+        Ok(())
+    }
+
+    /// Turn off vault mode, deleting the vault file. Callers must have moved the
+    /// entries elsewhere first; the vault has to be unlocked.
+    pub fn disable(&mut self) -> Result<()> {
+        if !self.is_enabled() {
+            return Err(VaultError::NotEnabled);
+        }
+        if self.unlocked.is_none() {
+            return Err(VaultError::Locked);
+        }
+        encrypted::delete(&self.path)?;
+        self.file = None;
+        self.unlocked = None;
+        Ok(())
+    }
+
+    /// The encrypted entries, for deriving a key outside the lock. Retries
+    /// reading a vault file that was unreadable so far.
+    fn sealed(&mut self) -> Result<EncryptedBlob> {
+        if self.unreadable {
+            self.file = read_file(&self.path)?;
+            self.unreadable = false;
+        }
+        self.file.as_ref().map(|f| f.data.clone()).ok_or(VaultError::NotEnabled)
+    }
+
+    /// Hold an unlocked key, unless the vault was rewritten since `sealed` was read
+    fn install(&mut self, sealed: &EncryptedBlob, key: DerivedKey, entries: HashMap<String, String>) -> Result<()> {
+        if self.sealed()? != *sealed {
+            return Err(VaultError::Changed);
+        }
+        self.unlocked = Some(Unlocked { key, entries });
+        self.touch();
+        Ok(())
+    }
+
+    /// Drop the derived key and decrypted entries from memory
+    pub fn lock(&mut self) {
+        self.unlocked = None;
+    }
+
+    /// Lock if the vault has been idle longer than its auto-lock timeout. Returns true if it locked.
+    pub fn lock_if_idle(&mut self) -> bool {
+        let timeout = match self.file.as_ref().and_then(|f| f.auto_lock_minutes) {
+            Some(minutes) => Duration::from_secs(minutes * 60),
+            None => return false,
+        };
+        if self.unlocked.is_some() && self.last_activity.elapsed() >= timeout {
+            self.lock();
+            return true;
+        }
+        false
+    }
+
+    pub fn set_auto_lock(&mut self, minutes: Option<u64>) -> Result<()> {
+        let file = self.file.as_mut().ok_or(VaultError::NotEnabled)?;
+        file.auto_lock_minutes = minutes.filter(|m| *m > 0);
+        let json = serde_json::to_string_pretty(file)?;
+        atomic::write_atomic(&self.path, json)?;
+        Ok(())
+    }
+
+    /// Re-seal the entries under `key` (a new passphrase)
+    fn rekey(&mut self, sealed: &EncryptedBlob, key: DerivedKey, entries: HashMap<String, String>) -> Result<()> {
+        self.install(sealed, key, entries)?;
+        let auto_lock = self.file.as_ref().and_then(|f| f.auto_lock_minutes);
+        self.persist(auto_lock)
+    }
+
+    pub fn get(&mut self, key: &str) -> Result<Option<String>> {
+        let unlocked = self.unlocked.as_ref().ok_or(VaultError::Locked)?;
+        let value = unlocked.entries.get(key).cloned();
+        self.touch();
+        Ok(value)
+    }
+
+    /// Snapshot of all entries (for backups)
+    pub fn entries(&mut self) -> Result<HashMap<String, String>> {
+        let unlocked = self.unlocked.as_ref().ok_or(VaultError::Locked)?;
+        let entries = unlocked.entries.clone();
+        self.touch();
+        Ok(entries)
+    }
+
+    pub fn store(&mut self, key: &str, value: &str) -> Result<()> {
+        let unlocked = self.unlocked.as_mut().ok_or(VaultError::Locked)?;
+        unlocked.entries.insert(key.to_string(), value.to_string());
+        self.touch();
+        let auto_lock = self.file.as_ref().and_then(|f| f.auto_lock_minutes);
+        self.persist(auto_lock)
+    }
+
+    pub fn delete(&mut self, key: &str) -> Result<()> {
+        let unlocked = self.unlocked.as_mut().ok_or(VaultError::Locked)?;
+        if unlocked.entries.remove(key).is_none() {
+            return Ok(());
+        }
+        self.touch();
+        let auto_lock = self.file.as_ref().and_then(|f| f.auto_lock_minutes);
+        self.persist(auto_lock)
+    }
+
+    fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    /// Re-seal the unlocked entries and write the vault file atomically
+    fn persist(&mut self, auto_lock_minutes: Option<u64>) -> Result<()> {
+        let unlocked = self.unlocked.as_ref().ok_or(VaultError::Locked)?;
+        let data = encrypted::encrypt_blob(&unlocked.entries, KeyMaterial::Derived(&unlocked.key), self.params)?;
+        let file = VaultFile {
+            version: VAULT_FORMAT_VERSION,
+            auto_lock_minutes,
+            entry_keys: unlocked.entries.keys().cloned().collect(),
+            data,
+        };
+        atomic::write_atomic(&self.path, serde_json::to_string_pretty(&file)?)?;
+        self.file = Some(file);
+        Ok(())
+    }
+}
+
+/// Read the vault file; `None` when there is none. A file that doesn't parse is
+/// left in place like one that can't be read: it is the only copy of the entries.
+fn read_file(path: &Path) -> Result<Option<VaultFile>> {
+    let loaded = atomic::load_with(path, "vault", |contents| {
+        serde_json::from_str(contents).map_err(atomic::Rejected::Keep)
+    });
+    match loaded {
+        atomic::Loaded::Found(file) => Ok(Some(file)),
+        atomic::Loaded::Missing => Ok(None),
+        atomic::Loaded::Quarantined | atomic::Loaded::Kept => Err(VaultError::Unreadable),
+    }
+}
+
+// Credentials are read through free functions (see `storage::keyring`), so the
+// vault is process-wide rather than Tauri-managed state.
+static VAULT: OnceLock<Mutex<Vault>> = OnceLock::new();
+
+/// Load the vault from the app data dir. Call once during setup.
+pub fn init(app_data_dir: &Path) {
+    if VAULT.set(Mutex::new(Vault::load(app_data_dir))).is_err() {
+        log::warn!("Vault already initialized");
+    }
+}
+
+/// Run a closure against the vault
+pub fn with_vault<T>(f: impl FnOnce(&mut Vault) -> T) -> Result<T> {
+    let vault = VAULT.get().ok_or(VaultError::NotInitialized)?;
+    let mut guard = vault.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(f(&mut guard))
+}
+
+/// Derive the key that sealed `sealed` and decrypt its entries
+fn open(sealed: &EncryptedBlob, passphrase: &str) -> Result<(DerivedKey, HashMap<String, String>)> {
+    let key = DerivedKey::for_blob(sealed, passphrase)?;
+    let entries = encrypted::decrypt_blob(sealed, KeyMaterial::Derived(&key)).map_err(|e| match e {
+        EncryptedStorageError::Decryption => VaultError::WrongPassphrase,
+        other => other.into(),
+    })?;
+    Ok((key, entries))
+}
+
+/// Run slow key derivation on a blocking thread
+async fn derive<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| VaultError::Task(e.to_string()))?
+}
+
+/// Unlock the vault without holding its lock during key derivation
+pub async fn unlock(passphrase: String) -> Result<()> {
+    let sealed = with_vault(|v| v.sealed())??;
+    let opened = sealed.clone();
+    let (key, entries) = derive(move || open(&opened, &passphrase)).await?;
+    with_vault(|v| v.install(&sealed, key, entries))?
+}
+
+/// Turn on vault mode for `entries` under a new master passphrase
+pub async fn enable(passphrase: String, entries: HashMap<String, String>) -> Result<()> {
+    if passphrase.is_empty() {
+        return Err(VaultError::EmptyPassphrase);
+    }
+    let params = with_vault(|v| if v.is_enabled() { Err(VaultError::AlreadyEnabled) } else { Ok(v.params) })??;
+    let key = derive(move || Ok(DerivedKey::generate(&passphrase, params)?)).await?;
+    with_vault(|v| v.enable_with_key(key, entries))?
+}
+
+/// Verify the current passphrase and re-seal the vault under a new one
+pub async fn change_passphrase(current: String, new: String) -> Result<()> {
+    if new.is_empty() {
+        return Err(VaultError::EmptyPassphrase);
+    }
+    let (sealed, params) = with_vault(|v| v.sealed().map(|s| (s, v.params)))??;
+    let opened = sealed.clone();
+    let (key, entries) = derive(move || {
+        let (_, entries) = open(&opened, &current)?;
+        Ok((DerivedKey::generate(&new, params)?, entries))
+    })
+    .await?;
+    with_vault(|v| v.rekey(&sealed, key, entries))?
+}
+
+/// Run a closure only when vault mode is enabled; `None` means use the regular store
+pub fn with_enabled_vault<T>(f: impl FnOnce(&mut Vault) -> Result<T>) -> Option<Result<T>> {
+    with_vault(|v| if v.is_enabled() { Some(f(v)) } else { None })
+        .ok()
+        .flatten()
+}
+
+/// Whether a provider should be paused because its credentials are in the locked vault
+pub fn is_provider_locked(provider: &str) -> bool {
+    with_vault(|v| v.is_locked() && v.holds_provider(provider)).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    impl Vault {
+        fn enable(&mut self, passphrase: &str, entries: HashMap<String, String>) -> Result<()> {
+            let key = DerivedKey::generate(passphrase, self.params)?;
+            self.enable_with_key(key, entries)
+        }
+
+        fn unlock(&mut self, passphrase: &str) -> Result<()> {
+            let sealed = self.sealed()?;
+            let (key, entries) = open(&sealed, passphrase)?;
+            self.install(&sealed, key, entries)
+        }
+    }
+
+    fn test_vault(name: &str) -> Vault {
+        let dir = std::env::temp_dir().join(format!("limitswatcher-vault-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut vault = Vault::load(&dir);
+        vault.params = KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+        vault
+    }
+
+    #[test]
+    fn test_lock_unlock_cycle() {
+        let mut vault = test_vault("cycle");
+        let entries = HashMap::from([
+            ("copilot_access_token".to_string(), "gho_123".to_string()),
+            (keys::notification_sink("team"), "{}".to_string()),
+        ]);
+        vault.enable("correct horse", entries).unwrap();
+        assert_eq!(vault.providers(), vec!["copilot".to_string()]);
+        assert!(vault.holds_provider("copilot"));
+        assert!(!vault.holds_provider("gemini"));
+
+        vault.lock();
+        assert!(vault.is_locked());
+        assert!(matches!(vault.get("copilot_access_token"), Err(VaultError::Locked)));
+        assert!(matches!(vault.unlock("wrong"), Err(VaultError::WrongPassphrase)));
+
+        // Reload from disk to make sure nothing depends on in-memory state
+        let mut reloaded = Vault::load(vault.path.parent().unwrap());
+        assert!(matches!(reloaded.disable(), Err(VaultError::Locked)));
+        reloaded.unlock("correct horse").unwrap();
+        assert_eq!(reloaded.get("copilot_access_token").unwrap().as_deref(), Some("gho_123"));
+
+        // A key derived from an outdated file must not be installed
+        let stale = reloaded.sealed().unwrap();
+        let (key, entries) = open(&stale, "correct horse").unwrap();
+        reloaded.store("gemini_oauth_token", "ya29").unwrap();
+        assert!(matches!(reloaded.install(&stale, key, entries), Err(VaultError::Changed)));
+
+        reloaded.disable().unwrap();
+        assert!(!reloaded.is_enabled());
+        assert!(!reloaded.path.exists());
+        let _ = fs::remove_dir_all(vault.path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_unreadable_vault_stays_locked() {
+        let dir = test_vault("unreadable").path.parent().unwrap().to_path_buf();
+        // A directory in place of the file fails to read with something other than NotFound
+        fs::create_dir_all(dir.join(VAULT_FILE_NAME)).unwrap();
+        let mut vault = Vault::load(&dir);
+        vault.params = KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+
+        assert!(vault.is_enabled() && vault.is_locked());
+        assert!(vault.holds_provider("copilot"));
+        assert!(matches!(vault.get("copilot_access_token"), Err(VaultError::Locked)));
+        assert!(matches!(vault.enable("pw", HashMap::new()), Err(VaultError::AlreadyEnabled)));
+        assert!(matches!(vault.unlock("pw"), Err(VaultError::Unreadable)));
+        assert!(vault.path.is_dir());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_corrupt_vault_stays_locked() {
+        let dir = test_vault("corrupt").path.parent().unwrap().to_path_buf();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(VAULT_FILE_NAME);
+        fs::write(&path, "{ not json").unwrap();
+        let mut vault = Vault::load(&dir);
+        vault.params = KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+
+        assert!(vault.is_enabled() && vault.is_locked());
+        assert!(matches!(vault.store("copilot_access_token", "gho_1"), Err(VaultError::Locked)));
+        assert!(matches!(vault.enable("pw", HashMap::new()), Err(VaultError::AlreadyEnabled)));
+        assert!(matches!(vault.unlock("pw"), Err(VaultError::Unreadable)));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
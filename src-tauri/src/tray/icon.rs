@@ -0,0 +1,306 @@
+//! Tray icon rendering
+//!
+//! Draws one or two horizontal usage bars (session above weekly) into a small
+//! RGBA bitmap, colored by severity, with a corner dot when data is stale or a
+//! refresh failed. Monochrome icons use black with alpha only, so the OS can
+//! tint them as template images.
+
+use serde::{Deserialize, Serialize};
+
+use crate::notifications::rules::Severity;
+
+/// Icon edge length in pixels
+pub const ICON_SIZE: u32 = 32;
+
+const BAR_LEFT: u32 = 2;
+const BAR_WIDTH: u32 = 28;
+const BORDER: u32 = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayTheme {
+    /// Follow the system: monochrome on macOS, otherwise the window theme
+    #[default]
+    Auto,
+    /// Dark outlines for light menu bars
+    Light,
+    /// Light outlines for dark taskbars
+    Dark,
+    Monochrome,
+}
+
+/// Corner marker drawn over the bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    /// Data predates a resume or network change and hasn't been refreshed
+    Stale,
+    /// The last refresh failed or needs re-authentication
+    Error,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bar {
+    pub percent: f64,
+    pub severity: Severity,
+}
+
+/// What the icon shows: at most two bars, top to bottom
+#[derive(Debug, Clone, Default)]
+pub struct IconState {
+    pub bars: Vec<Bar>,
+    pub overlay: Option<Overlay>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaIcon {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+type Color = [u8; 4];
+
+const CLEAR: Color = [0, 0, 0, 0];
+
+struct Palette {
+    outline: Color,
+    track: Color,
+    ok: Color,
+    warning: Color,
+    critical: Color,
+    error: Color,
+}
+
+impl Palette {
+    fn for_theme(theme: TrayTheme) -> Self {
+        match theme {
+            TrayTheme::Auto | TrayTheme::Light => Self {
+                outline: [60, 60, 67, 255],
+                track: [60, 60, 67, 40],
+                ok: [40, 167, 69, 255],
+                warning: [230, 140, 0, 255],
+                critical: [220, 45, 40, 255],
+                error: [220, 45, 40, 255],
+            },
+            TrayTheme::Dark => Self {
+                outline: [235, 235, 245, 255],
+                track: [235, 235, 245, 50],
+                ok: [48, 209, 88, 255],
+                warning: [255, 159, 10, 255],
+                critical: [255, 69, 58, 255],
+                error: [255, 69, 58, 255],
+            },
+            TrayTheme::Monochrome => Self {
+                outline: [0, 0, 0, 255],
+                track: [0, 0, 0, 40],
+                ok: [0, 0, 0, 255],
+                warning: [0, 0, 0, 255],
+                critical: [0, 0, 0, 255],
+                error: [0, 0, 0, 255],
+            },
+        }
+    }
+
+    fn fill(&self, severity: Severity) -> Color {
+        match severity {
+            Severity::Info => self.ok,
+            Severity::Warning => self.warning,
+            Severity::Critical => self.critical,
+        }
+    }
+}
+
+impl RgbaIcon {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            rgba: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let i = ((y * self.width + x) * 4) as usize;
+        [self.rgba[i], self.rgba[i + 1], self.rgba[i + 2], self.rgba[i + 3]]
+    }
+
+    fn set(&mut self, x: u32, y: u32, color: Color) {
+        if x < self.width && y < self.height {
+            let i = ((y * self.width + x) * 4) as usize;
+            self.rgba[i..i + 4].copy_from_slice(&color);
+        }
+    }
+
+    fn rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color) {
+        for py in y..y + height {
+            for px in x..x + width {
+                self.set(px, py, color);
+            }
+        }
+    }
+
+    /// Pixels whose centers lie within `radius` of (`cx`, `cy`)
+    fn disc(&mut self, cx: f64, cy: f64, radius: f64, color: Color) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+                if dx * dx + dy * dy <= radius * radius {
+                    self.set(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Encode as an 8-bit RGBA PNG
+    pub fn to_png(&self) -> Vec<u8> {
+        let row = self.width as usize * 4;
+        let mut raw = Vec::with_capacity((row + 1) * self.height as usize);
+        for line in self.rgba.chunks(row) {
+            raw.push(0); // no filter
+            raw.extend_from_slice(line);
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+/// Draw the icon for `state`; `Auto` renders like `Light`
+pub fn render(state: &IconState, theme: TrayTheme) -> RgbaIcon {
+    let palette = Palette::for_theme(theme);
+    let mut icon = RgbaIcon::new(ICON_SIZE, ICON_SIZE);
+
+    let rows: &[(u32, u32)] = match state.bars.len() {
+        0 | 1 => &[(9, 14)],
+        _ => &[(4, 11), (17, 11)],
+    };
+    for (i, &(top, height)) in rows.iter().enumerate() {
+        icon.rect(BAR_LEFT, top, BAR_WIDTH, height, palette.outline);
+        let inner = BAR_WIDTH - 2 * BORDER;
+        icon.rect(BAR_LEFT + BORDER, top + BORDER, inner, height - 2 * BORDER, palette.track);
+
+        let Some(bar) = state.bars.get(i) else {
+            continue;
+        };
+        let mut fill = palette.fill(bar.severity);
+        if state.overlay == Some(Overlay::Stale) {
+            fill[3] /= 2;
+        }
+        let filled = (bar.percent.clamp(0.0, 100.0) / 100.0 * inner as f64).round() as u32;
+        icon.rect(BAR_LEFT + BORDER, top + BORDER, filled, height - 2 * BORDER, fill);
+    }
+
+    // A cleared ring sets the dot apart from the bar beneath it
+    let (cx, cy) = (ICON_SIZE as f64 - 6.0, 6.0);
+    match state.overlay {
+        Some(Overlay::Error) => {
+            icon.disc(cx, cy, 6.0, CLEAR);
+            icon.disc(cx, cy, 4.5, palette.error);
+        }
+        Some(Overlay::Stale) => {
+            icon.disc(cx, cy, 6.0, CLEAR);
+            icon.disc(cx, cy, 4.5, palette.outline);
+            icon.disc(cx, cy, 2.5, CLEAR);
+        }
+        None => {}
+    }
+    icon
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// zlib stream of uncompressed deflate blocks; icons are too small to bother compressing
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bars_colored_by_severity_with_overlay() {
+        let state = IconState {
+            bars: vec![
+                Bar { percent: 50.0, severity: Severity::Info },
+                Bar { percent: 95.0, severity: Severity::Critical },
+            ],
+            overlay: Some(Overlay::Error),
+        };
+        let icon = render(&state, TrayTheme::Dark);
+        let palette = Palette::for_theme(TrayTheme::Dark);
+
+        // Session bar is half filled, weekly almost full
+        assert_eq!(icon.pixel(5, 9), palette.ok);
+        assert_eq!(icon.pixel(20, 9), palette.track);
+        assert_eq!(icon.pixel(25, 23), palette.critical);
+        assert_eq!(icon.pixel(26, 6), palette.error);
+
+        let mono = render(&IconState { overlay: None, ..state }, TrayTheme::Monochrome);
+        assert!(mono.rgba.chunks(4).all(|px| px[..3] == [0, 0, 0]));
+        assert_eq!(mono.pixel(5, 9), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let state = IconState {
+            bars: vec![Bar { percent: 80.0, severity: Severity::Warning }],
+            overlay: Some(Overlay::Stale),
+        };
+        let icon = render(&state, TrayTheme::Light);
+        let png = icon.to_png();
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+
+        let decoded = tauri::image::Image::from_bytes(&png).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (ICON_SIZE, ICON_SIZE));
+        assert_eq!(decoded.rgba(), &icon.rgba[..]);
+    }
+}
@@ -0,0 +1,229 @@
+//! System tray management with dynamic icons and menus
+
+pub mod icon;
+
+use std::sync::Arc;
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::image::Image;
+use tokio::sync::RwLock;
+
+use crate::config::{ConfigManager, Thresholds};
+use crate::notifications::rules::Severity;
+use crate::providers::retry::RetryState;
+use crate::providers::ProviderRegistry;
+use crate::scheduler::Scheduler;
+use crate::storage::{CacheManager, UsageData};
+use icon::{Bar, IconState, Overlay, TrayTheme};
+
+const TRAY_ID: &str = "main";
+
+/// Usage at or above this is drawn as critical
+const CRITICAL_PERCENT: f64 = 95.0;
+
+pub fn init<R: Runtime>(app: &tauri::App<R>) -> tauri::Result<()> {
+    let quit = MenuItem::with_id(app, "quit", "Quit LimitsWatcher", true, None::<&str>)?;
+    let show = MenuItem::with_id(app, "show", "Show Dashboard", true, None::<&str>)?;
+    let refresh = MenuItem::with_id(app, "refresh", "Refresh All", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    
+    let menu = Menu::with_items(app, &[&show, &refresh, &separator, &quit])?;
+    
+    // Load tray icon
+    // Note: We use the default icon path for now, but in production this should be bundled
+    // and loaded more robustly.
+    let icon = Image::from_path("icons/icon.png")
+        .unwrap_or_else(|_| Image::from_bytes(include_bytes!("../../icons/icon.png")).unwrap());
+    
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(icon)
+        .menu(&menu)
+        .tooltip("LimitsWatcher - AI Usage Tracker")
+        .on_menu_event(|app, event| {
+            match event.id.as_ref() {
+                "quit" => {
+                    app.exit(0);
+                }
+                "show" => {
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                }
+                "refresh" => {
+                    // Refresh in the backend; the frontend follows provider-updated events
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = crate::scheduler::refresh_now(&app, None).await {
+                            log::warn!("Refresh from tray failed to start: {}", e);
+                        }
+                    });
+                }
+                _ => {}
+            }
+        })
+        .on_tray_icon_event(|tray, event| {
+            match event {
+                TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    .. 
+                } => {
+                    // Left click: show main window
+                    let app = tray.app_handle();
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                }
+                _ => {}
+            }
+        })
+        .build(app)?;
+    
+    Ok(())
+}
+
+/// Update tray icon based on usage status
+pub fn update_icon<R: Runtime>(app: &AppHandle<R>, status: TrayStatus, state: &IconState, theme: TrayTheme) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let theme = resolve_theme(app, theme);
+        let icon = icon::render(state, theme);
+        if let Err(e) = tray.set_icon(Some(Image::new_owned(icon.rgba, icon.width, icon.height))) {
+            log::warn!("Failed to update tray icon: {}", e);
+        }
+        // Lets macOS tint the icon to match the menu bar
+        let _ = tray.set_icon_as_template(theme == TrayTheme::Monochrome);
+
+        let tooltip = match status {
+            TrayStatus::Ok { summary } => format!("LimitsWatcher\n{}", summary),
+            TrayStatus::Warning { message } => format!("⚠️ {}", message),
+            TrayStatus::Error { message } => format!("❌ {}", message),
+        };
+        let _ = tray.set_tooltip(Some(&tooltip));
+    }
+}
+
+/// Redraw the tray icon from cached usage; call after every refresh
+pub async fn refresh<R: Runtime>(app: &AppHandle<R>) {
+    let theme = match app.try_state::<Arc<RwLock<ConfigManager>>>() {
+        Some(config) => config.read().await.get().tray_theme,
+        None => TrayTheme::Auto,
+    };
+    let (status, state) = current_state(app).await;
+    update_icon(app, status, &state, theme);
+}
+
+/// `Auto` becomes a template icon on macOS and follows the window theme elsewhere
+pub fn resolve_theme<R: Runtime>(app: &AppHandle<R>, theme: TrayTheme) -> TrayTheme {
+    if theme != TrayTheme::Auto {
+        return theme;
+    }
+    if cfg!(target_os = "macos") {
+        return TrayTheme::Monochrome;
+    }
+    match app.get_webview_window("main").and_then(|w| w.theme().ok()) {
+        Some(tauri::Theme::Dark) => TrayTheme::Dark,
+        _ => TrayTheme::Light,
+    }
+}
+
+fn percent(used: u64, limit: u64) -> Option<f64> {
+    (limit > 0).then(|| used as f64 / limit as f64 * 100.0)
+}
+
+fn severity(percent: f64, warning: f64) -> Severity {
+    if percent >= CRITICAL_PERCENT {
+        Severity::Critical
+    } else if percent >= warning {
+        Severity::Warning
+    } else {
+        Severity::Info
+    }
+}
+
+/// Session and weekly bars of one provider
+fn bars(usage: &UsageData, thresholds: &Thresholds) -> Vec<Bar> {
+    [
+        (percent(usage.session_used, usage.session_limit), thresholds.session_warning_percent),
+        (percent(usage.weekly_used, usage.weekly_limit), thresholds.weekly_warning_percent),
+    ]
+    .into_iter()
+    .filter_map(|(percent, warning)| percent.map(|p| Bar { percent: p, severity: severity(p, warning) }))
+    .collect()
+}
+
+/// Bars for the most constrained enabled provider, plus the tooltip status
+pub async fn current_state<R: Runtime>(app: &AppHandle<R>) -> (TrayStatus, IconState) {
+    let thresholds = match app.try_state::<Arc<RwLock<ConfigManager>>>() {
+        Some(config) => config.read().await.get().thresholds.clone(),
+        None => Thresholds::default(),
+    };
+    let enabled: Vec<String> = match app.try_state::<Arc<RwLock<ProviderRegistry>>>() {
+        Some(registry) => registry.read().await.enabled_providers().into_iter().map(|(id, _)| id).collect(),
+        None => Vec::new(),
+    };
+    let Some(cache) = app.try_state::<Arc<RwLock<CacheManager>>>() else {
+        return (TrayStatus::Ok { summary: "No usage data yet".to_string() }, IconState::default());
+    };
+
+    let mut failing = Vec::new();
+    if let Some(scheduler) = app.try_state::<Arc<Scheduler>>() {
+        for id in &enabled {
+            if scheduler.retry_status(id).await.is_some_and(|s| s.state != RetryState::Ok) {
+                failing.push(id.clone());
+            }
+        }
+    }
+
+    let cache = cache.read().await;
+    let mut constrained: Option<(&String, Vec<Bar>)> = None;
+    for id in &enabled {
+        let Some(usage) = cache.get(id) else {
+            continue;
+        };
+        if usage.error.is_some() && !failing.contains(id) {
+            failing.push(id.clone());
+        }
+        let provider_bars = bars(usage, &thresholds);
+        let worst = |bars: &[Bar]| bars.iter().map(|b| b.percent).fold(f64::NEG_INFINITY, f64::max);
+        if !provider_bars.is_empty() && constrained.as_ref().is_none_or(|(_, b)| worst(&provider_bars) > worst(b)) {
+            constrained = Some((id, provider_bars));
+        }
+    }
+
+    let (provider, bars) = match constrained {
+        Some((id, bars)) => (Some(id.as_str()), bars),
+        None => (None, Vec::new()),
+    };
+    let overlay = if !failing.is_empty() {
+        Some(Overlay::Error)
+    } else if provider.is_some_and(|id| cache.is_stale(id)) {
+        Some(Overlay::Stale)
+    } else {
+        None
+    };
+
+    let summary = match provider {
+        Some(id) => {
+            let parts: Vec<String> = bars.iter().map(|b| format!("{:.0}%", b.percent)).collect();
+            format!("{}: {}", id, parts.join(" / "))
+        }
+        None => "No usage data yet".to_string(),
+    };
+    let status = if !failing.is_empty() {
+        TrayStatus::Error { message: format!("Refresh failed: {}", failing.join(", ")) }
+    } else if bars.iter().any(|b| b.severity != Severity::Info) {
+        TrayStatus::Warning { message: summary }
+    } else {
+        TrayStatus::Ok { summary }
+    };
+    (status, IconState { bars, overlay })
+}
+
+pub enum TrayStatus {
+    Ok { summary: String },
+    Warning { message: String },
+    Error { message: String },
+}
\ No newline at end of file